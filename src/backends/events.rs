@@ -0,0 +1,105 @@
+//! Event subscriptions shared by the storage backends.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_core::{ready, Stream};
+
+use crate::models::hash::Hash;
+use crate::models::storage::StorageEvent;
+
+/// Description passed to update handlers when a revision was stored.
+pub const STORED: &str = "stored";
+
+/// Description passed to update handlers when the context of a revision changed.
+pub const CONTEXT_UPDATED: &str = "context updated";
+
+/// Number of events buffered for each subscriber before it is disconnected.
+pub(crate) const CAPACITY: usize = 32;
+
+/// The event streams subscribed to a storage.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    senders: Mutex<Vec<Subscriber>>,
+}
+
+/// The sending half of a [`Subscription`].
+struct Subscriber {
+    sender: mpsc::UnboundedSender<StorageEvent>,
+    buffered: Arc<AtomicUsize>,
+}
+
+/// The event stream of a subscriber.
+pub(crate) struct Subscription {
+    receiver: mpsc::UnboundedReceiver<StorageEvent>,
+    buffered: Arc<AtomicUsize>,
+}
+
+impl Subscribers {
+    /// Adds a subscriber, receiving every event published after this call.
+    pub(crate) fn subscribe(&self) -> Subscription {
+        let (sender, receiver) = mpsc::unbounded();
+        let buffered = Arc::new(AtomicUsize::new(0));
+        lock(&self.senders).push(Subscriber {
+            sender,
+            buffered: buffered.clone(),
+        });
+        Subscription { receiver, buffered }
+    }
+
+    /// Sends `events` to every subscriber in order, without waiting. A subscriber with
+    /// [`CAPACITY`] events buffered is sent [`StorageEvent::Lagged`] instead and
+    /// disconnected, so a stream that is not polled cannot hold up the storage;
+    /// subscribers whose stream was dropped are removed.
+    pub(crate) fn publish(&self, events: &[StorageEvent]) {
+        lock(&self.senders)
+            .retain(|subscriber| events.iter().all(|event| subscriber.send(*event)));
+    }
+
+    /// Number of subscribers, including dropped ones not yet cleaned up.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        lock(&self.senders).len()
+    }
+}
+
+impl Subscriber {
+    /// Sends `event`, or [`StorageEvent::Lagged`] if the buffer is full. Returns whether
+    /// the subscriber stays connected.
+    fn send(&self, event: StorageEvent) -> bool {
+        let lagged = self.buffered.fetch_add(1, Ordering::AcqRel) >= CAPACITY;
+        let event = if lagged { StorageEvent::Lagged } else { event };
+        self.sender.unbounded_send(event).is_ok() && !lagged
+    }
+}
+
+impl Stream for Subscription {
+    type Item = StorageEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StorageEvent>> {
+        let event = ready!(Pin::new(&mut self.receiver).poll_next(cx));
+        if event.is_some() {
+            self.buffered.fetch_sub(1, Ordering::AcqRel);
+        }
+        Poll::Ready(event)
+    }
+}
+
+/// The events of storing `hash`, following `previous`. `forked` tells whether another
+/// stored revision already follows `previous`.
+pub(crate) fn stored(hash: Hash, previous: Option<Hash>, forked: bool) -> Vec<StorageEvent> {
+    let mut events = vec![StorageEvent::RevisionStored(hash)];
+    events.extend(previous.map(|previous| match forked {
+        false => StorageEvent::BranchExtended { previous, hash },
+        true => StorageEvent::BranchForked { previous, hash },
+    }));
+    events
+}
+
+/// Locks `mutex`, ignoring poisoning: all data is valid after every single update.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,714 @@
+//! Content-addressed [`Storage`] on the filesystem.
+//!
+//! Layout below the root directory:
+//! - `revisions/<first two hex digits>/<verification_hash>.json`: the revision.
+//! - `revisions/<first two hex digits>/<verification_hash>.context.json`: its context.
+//! - `branches/<genesis_hash>.json`: all revisions descending from a genesis revision,
+//!   parents before children.
+//! - `orphans.json`: revision files left out of the branch index, as the revision they
+//!   follow is not stored.
+//!
+//! Every file is written to a temporary file first, synced, renamed into place and its
+//! directory synced. The context is written before the revision and the branch index
+//! after it, so a revision file is only present once it is complete, and the branch index
+//! can always be rebuilt from the revision files with [`FsStorage::rebuild_index`].
+//!
+//! Files are accessed on a worker thread, so the futures returned by [`Storage`] never
+//! block the runtime polling them. The worker stops once the storage is dropped. Only one
+//! `FsStorage` may use a root directory at a time.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use futures_core::Stream;
+
+use super::events::{self, Subscribers};
+use super::worker::Worker;
+
+use crate::models::branch::Branch;
+use crate::models::hash::Hash;
+use crate::models::revision::Revision;
+use crate::models::storage::{Storage, StorageEvent, UpdateHandlerError};
+
+pub use super::events::{CONTEXT_UPDATED, STORED};
+
+const REVISIONS_DIR: &str = "revisions";
+const BRANCHES_DIR: &str = "branches";
+const ORPHANS_FILE: &str = "orphans.json";
+const CONTEXT_SUFFIX: &str = ".context.json";
+const JSON_SUFFIX: &str = ".json";
+const TMP_SUFFIX: &str = ".tmp";
+
+/// A [`Storage`] keeping every revision as a JSON file named by its `verification_hash`.
+pub struct FsStorage<C> {
+    root: PathBuf,
+    worker: Worker<Files>,
+    subscribers: Subscribers,
+    context: PhantomData<fn() -> C>,
+}
+
+/// The files below the root directory, owned by the worker thread.
+struct Files {
+    root: PathBuf,
+    index: Index,
+}
+
+/// Which genesis revision each stored revision descends from.
+#[derive(Default)]
+struct Index {
+    genesis_of: HashMap<Hash, Hash>,
+    branches: BTreeMap<Hash, Vec<Hash>>,
+    orphans: BTreeSet<Hash>,
+}
+
+impl<C> FsStorage<C> {
+    /// Opens the storage in `root`, creating the directory if needed.
+    ///
+    /// The branch index is rebuilt from the revision files if it is missing, unreadable or
+    /// does not cover every revision file, e.g. after a crash during [`Storage::store`].
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, FsStorageError> {
+        let root = root.into();
+        for dir in [REVISIONS_DIR, BRANCHES_DIR] {
+            let path = root.join(dir);
+            std::fs::create_dir_all(&path).map_err(|source| FsStorageError::Io { path, source })?;
+        }
+        let mut files = Files {
+            root: root.clone(),
+            index: Index::default(),
+        };
+        match files.load_current_index()? {
+            Some(index) => files.index = index,
+            None => {
+                files.rebuild_index()?;
+            }
+        }
+        let worker = Worker::spawn("aqua-fs", files).map_err(|source| FsStorageError::Io {
+            path: root.clone(),
+            source,
+        })?;
+        Ok(FsStorage {
+            root,
+            worker,
+            subscribers: Subscribers::default(),
+            context: PhantomData,
+        })
+    }
+
+    /// The root directory of the storage.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Hashes of all genesis revisions.
+    pub async fn genesis_hashes(&self) -> Result<Vec<Hash>, FsStorageError> {
+        self.run(|files| Ok(files.index.branches.keys().copied().collect()))
+            .await
+    }
+
+    /// Rebuilds the branch index from the revision files and removes leftover temporary
+    /// files. Orphaned revisions whose previous revision has been stored since are
+    /// indexed again.
+    ///
+    /// # Returns
+    /// The revisions left out of the index, as the revision they follow is not stored.
+    pub async fn rebuild_index(&self) -> Result<Vec<Hash>, FsStorageError> {
+        self.run(Files::rebuild_index).await
+    }
+
+    /// Runs `f` on the worker thread.
+    fn run<T, F>(&self, f: F) -> impl Future<Output = Result<T, FsStorageError>> + Send + Sync
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Files) -> Result<T, FsStorageError> + Send + Sync + 'static,
+    {
+        let result = self.worker.run(f);
+        async move { result.await.unwrap_or(Err(FsStorageError::WorkerStopped)) }
+    }
+}
+
+impl Files {
+    /// Reads the branch index, `None` if it does not list exactly the revision files.
+    fn load_current_index(&self) -> Result<Option<Index>, FsStorageError> {
+        let revisions: HashSet<Hash> = self.scan_revisions()?.into_iter().collect();
+        Ok(self.load_index().ok().filter(|index| {
+            let indexed: HashSet<Hash> = index
+                .genesis_of
+                .keys()
+                .chain(&index.orphans)
+                .copied()
+                .collect();
+            indexed == revisions
+        }))
+    }
+
+    fn rebuild_index(&mut self) -> Result<Vec<Hash>, FsStorageError> {
+        let mut previous_of = HashMap::new();
+        for hash in self.scan_revisions()? {
+            let rev = self.read_revision_file(hash)?;
+            previous_of.insert(hash, rev.metadata.previous_verification_hash);
+        }
+
+        let mut rebuilt = Index::default();
+        let mut depth_of = HashMap::new();
+        for &hash in previous_of.keys() {
+            let mut chain = vec![hash];
+            let mut in_chain = HashSet::from([hash]);
+            let genesis = loop {
+                let current = *chain.last().expect("chain is never empty");
+                if let Some(&genesis) = rebuilt.genesis_of.get(&current) {
+                    chain.pop();
+                    break Some(genesis);
+                }
+                match previous_of.get(&current) {
+                    Some(None) => break Some(current),
+                    Some(Some(previous)) if in_chain.insert(*previous) => chain.push(*previous),
+                    _ => break None,
+                }
+            };
+            let Some(genesis) = genesis else {
+                rebuilt.orphans.insert(hash);
+                continue;
+            };
+            let depth = chain
+                .last()
+                .and_then(|current| previous_of[current])
+                .map_or(0, |previous| depth_of[&previous] + 1);
+            for (offset, current) in chain.into_iter().rev().enumerate() {
+                rebuilt.genesis_of.insert(current, genesis);
+                depth_of.insert(current, depth + offset);
+            }
+        }
+        for (&hash, &genesis) in &rebuilt.genesis_of {
+            rebuilt.branches.entry(genesis).or_default().push(hash);
+        }
+        for hashes in rebuilt.branches.values_mut() {
+            hashes.sort_by_key(|hash| (depth_of[hash], *hash));
+        }
+
+        let branches_dir = self.root.join(BRANCHES_DIR);
+        for entry in read_dir(&branches_dir)? {
+            let path = entry.path();
+            std::fs::remove_file(&path).map_err(|source| FsStorageError::Io { path, source })?;
+        }
+        for (genesis, hashes) in &rebuilt.branches {
+            self.write_branch(genesis, hashes)?;
+        }
+        write_json(&self.root.join(ORPHANS_FILE), &rebuilt.orphans)?;
+        let orphans = rebuilt.orphans.iter().copied().collect();
+        self.index = rebuilt;
+        Ok(orphans)
+    }
+
+    fn revision_path(&self, hash: &Hash, suffix: &str) -> PathBuf {
+        let name = hash.to_stackstr();
+        let name: &str = name.as_ref();
+        self.root
+            .join(REVISIONS_DIR)
+            .join(&name[..2])
+            .join(format!("{name}{suffix}"))
+    }
+
+    fn branch_path(&self, genesis: &Hash) -> PathBuf {
+        self.root
+            .join(BRANCHES_DIR)
+            .join(format!("{genesis}{JSON_SUFFIX}"))
+    }
+
+    /// Reads an indexed revision. Orphaned revisions are not found, as they are not listed.
+    fn read_revision(&self, hash: Hash) -> Result<Revision, FsStorageError> {
+        if !self.index.genesis_of.contains_key(&hash) {
+            return Err(FsStorageError::NotFound(hash));
+        }
+        self.read_revision_file(hash)
+    }
+
+    /// Reads the revision file of `hash`, whether it is indexed or not.
+    fn read_revision_file(&self, hash: Hash) -> Result<Revision, FsStorageError> {
+        let path = self.revision_path(&hash, JSON_SUFFIX);
+        let rev: Revision = read_json(&path).map_err(|e| match e {
+            FsStorageError::Io { source, .. } if source.kind() == std::io::ErrorKind::NotFound => {
+                FsStorageError::NotFound(hash)
+            }
+            e => e,
+        })?;
+        if rev.metadata.verification_hash != hash {
+            return Err(FsStorageError::Corrupt {
+                path,
+                found: rev.metadata.verification_hash,
+            });
+        }
+        Ok(rev)
+    }
+
+    fn read_context<C: serde::de::DeserializeOwned>(
+        &self,
+        hash: Hash,
+    ) -> Result<C, FsStorageError> {
+        if !self.index.genesis_of.contains_key(&hash) {
+            return Err(FsStorageError::NotFound(hash));
+        }
+        read_json(&self.revision_path(&hash, CONTEXT_SUFFIX))
+    }
+
+    /// Writes `rev` and `context` and adds `rev` to the index. Returns whether another
+    /// revision already follows the revision `rev` follows.
+    fn store<C: serde::Serialize>(
+        &mut self,
+        rev: &Revision,
+        context: &C,
+    ) -> Result<bool, FsStorageError> {
+        let hash = rev.metadata.verification_hash;
+        if self.index.genesis_of.contains_key(&hash) {
+            return Err(FsStorageError::AlreadyStored(hash));
+        }
+        let previous = rev.metadata.previous_verification_hash;
+        let genesis = match previous {
+            None => hash,
+            Some(previous) => *self
+                .index
+                .genesis_of
+                .get(&previous)
+                .ok_or(FsStorageError::MissingPrevious { previous })?,
+        };
+        write_json(&self.revision_path(&hash, CONTEXT_SUFFIX), context)?;
+        write_json(&self.revision_path(&hash, JSON_SUFFIX), rev)?;
+
+        let mut hashes = self
+            .index
+            .branches
+            .get(&genesis)
+            .cloned()
+            .unwrap_or_default();
+        let forked = match previous {
+            Some(previous) => self.is_followed(&hashes, previous)?,
+            None => false,
+        };
+        hashes.push(hash);
+        self.write_branch(&genesis, &hashes)?;
+        self.index.branches.insert(genesis, hashes);
+        self.index.genesis_of.insert(hash, genesis);
+        if self.index.orphans.remove(&hash) {
+            write_json(&self.root.join(ORPHANS_FILE), &self.index.orphans)?;
+        }
+        Ok(forked)
+    }
+
+    /// Whether a revision in `branch` follows `previous`. Revisions following `previous`
+    /// are listed after it, so only those are read.
+    fn is_followed(&self, branch: &[Hash], previous: Hash) -> Result<bool, FsStorageError> {
+        let start = branch
+            .iter()
+            .position(|hash| *hash == previous)
+            .map_or(0, |i| i + 1);
+        for hash in &branch[start..] {
+            let rev = self.read_revision(*hash)?;
+            if rev.metadata.previous_verification_hash == Some(previous) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn write_branch(&self, genesis: &Hash, hashes: &[Hash]) -> Result<(), FsStorageError> {
+        write_json(&self.branch_path(genesis), hashes)
+    }
+
+    /// Reads all branch index files and the list of orphans.
+    fn load_index(&self) -> Result<Index, FsStorageError> {
+        let mut index = Index {
+            orphans: read_json(&self.root.join(ORPHANS_FILE))?,
+            ..Index::default()
+        };
+        for entry in read_dir(&self.root.join(BRANCHES_DIR))? {
+            let path = entry.path();
+            let genesis = hash_of(&path, JSON_SUFFIX).ok_or_else(|| FsStorageError::Io {
+                path: path.clone(),
+                source: std::io::ErrorKind::InvalidData.into(),
+            })?;
+            let hashes: Vec<Hash> = read_json(&path)?;
+            for hash in &hashes {
+                index.genesis_of.insert(*hash, genesis);
+            }
+            index.branches.insert(genesis, hashes);
+        }
+        Ok(index)
+    }
+
+    /// Lists the hashes of all revision files, removing leftover temporary files.
+    fn scan_revisions(&self) -> Result<Vec<Hash>, FsStorageError> {
+        let mut hashes = Vec::new();
+        for shard in read_dir(&self.root.join(REVISIONS_DIR))? {
+            for entry in read_dir(&shard.path())? {
+                let path = entry.path();
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.ends_with(TMP_SUFFIX) {
+                    std::fs::remove_file(&path)
+                        .map_err(|source| FsStorageError::Io { path, source })?;
+                } else if !name.ends_with(CONTEXT_SUFFIX) {
+                    if let Some(hash) = hash_of(&path, JSON_SUFFIX) {
+                        hashes.push(hash);
+                    }
+                }
+            }
+        }
+        Ok(hashes)
+    }
+}
+
+impl<C> Storage for FsStorage<C>
+where
+    C: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    type Error = FsStorageError;
+    type Context = C;
+
+    async fn get_context(&self, hash: Hash) -> Result<C, Self::Error> {
+        self.run(move |files| files.read_context(hash)).await
+    }
+
+    /// Stores `rev`. The revision it follows has to be stored already.
+    async fn store(&self, rev: Revision, context: C) -> Result<(), Self::Error> {
+        let hash = rev.metadata.verification_hash;
+        let previous = rev.metadata.previous_verification_hash;
+        let forked = self.run(move |files| files.store(&rev, &context)).await?;
+        self.subscribers
+            .publish(&events::stored(hash, previous, forked));
+        Ok(())
+    }
+
+    fn read(
+        &self,
+        hash: Hash,
+    ) -> impl Future<Output = Result<Revision, Self::Error>> + Send + Sync {
+        self.run(move |files| files.read_revision(hash))
+    }
+
+    /// Builds the branch ending in `hash` by following `previous_verification_hash` back
+    /// to the genesis revision. Hashes are ordered genesis first, the context is the one
+    /// stored with `hash`.
+    async fn get_branch(&self, hash: Hash) -> Result<Branch<C>, Self::Error> {
+        self.run(move |files| {
+            let metadata = files.read_context(hash)?;
+            let mut hashes = vec![hash];
+            let mut current = files.read_revision(hash)?;
+            while let Some(previous) = current.metadata.previous_verification_hash {
+                hashes.push(previous);
+                current = files.read_revision(previous)?;
+            }
+            hashes.reverse();
+            Ok(Branch { metadata, hashes })
+        })
+        .await
+    }
+
+    /// Lists all indexed revisions, grouped by genesis revision, parents before children.
+    async fn list(&self) -> Result<Vec<Hash>, Self::Error> {
+        self.run(|files| Ok(files.index.branches.values().flatten().copied().collect()))
+            .await
+    }
+
+    fn subscribe(&self) -> impl Stream<Item = StorageEvent> + Send + Unpin + 'static {
+        self.subscribers.subscribe()
+    }
+}
+
+/// Parses the hash in a file name ending with `suffix`.
+fn hash_of(path: &Path, suffix: &str) -> Option<Hash> {
+    path.file_name()?
+        .to_str()?
+        .strip_suffix(suffix)?
+        .parse()
+        .ok()
+}
+
+fn read_dir(path: &Path) -> Result<Vec<std::fs::DirEntry>, FsStorageError> {
+    let io_error = |source| FsStorageError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    std::fs::read_dir(path)
+        .map_err(io_error)?
+        .collect::<Result<_, _>>()
+        .map_err(io_error)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, FsStorageError> {
+    let data = std::fs::read(path).map_err(|source| FsStorageError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_slice(&data).map_err(|source| FsStorageError::Json {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Writes `value` to a temporary file next to `path` and renames it into place, syncing
+/// the file and then the directories whose entries changed.
+fn write_json<T: serde::Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), FsStorageError> {
+    let data = serde_json::to_vec_pretty(value).map_err(|source| FsStorageError::Json {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(TMP_SUFFIX);
+    let tmp = PathBuf::from(tmp);
+    let write = || -> std::io::Result<()> {
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let created = !parent.exists();
+        std::fs::create_dir_all(parent)?;
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        sync_dir(parent)?;
+        match parent.parent() {
+            Some(grandparent) if created => sync_dir(grandparent),
+            _ => Ok(()),
+        }
+    };
+    write().map_err(|source| FsStorageError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Syncs the entries of the directory at `path`. Skipped outside of Unix, where
+/// directories cannot be opened as files.
+fn sync_dir(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    std::fs::File::open(path)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Error types of [`FsStorage`].
+#[derive(thiserror::Error, Debug)]
+pub enum FsStorageError {
+    /// Reading or writing a file failed.
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// A file does not hold valid JSON of the expected type.
+    #[error("{}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// A revision file holds a revision with a different `verification_hash`.
+    #[error("{} holds revision {found}", path.display())]
+    Corrupt { path: PathBuf, found: Hash },
+
+    /// No revision with this `verification_hash` is stored.
+    #[error("revision {0} not found")]
+    NotFound(Hash),
+
+    /// A revision with this `verification_hash` is already stored.
+    #[error("revision {0} is already stored")]
+    AlreadyStored(Hash),
+
+    /// The revision a revision follows is not stored.
+    #[error("previous revision {previous} not found")]
+    MissingPrevious { previous: Hash },
+
+    /// The worker thread accessing the files is gone.
+    #[error("file system worker stopped")]
+    WorkerStopped,
+
+    /// The future returned by the deprecated `update_handler` stopped.
+    #[error(transparent)]
+    UpdateHandler(#[from] UpdateHandlerError),
+}
+
+/// A temporary storage root, removed when dropped.
+#[cfg(test)]
+struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    fn new() -> Self {
+        TempDir(
+            std::env::temp_dir().join(format!("aqua-fs-storage-{:016x}", rand::random::<u64>())),
+        )
+    }
+
+    /// The files of a storage in this directory, to inspect and tamper with them.
+    fn files(&self) -> Files {
+        Files {
+            root: self.0.clone(),
+            index: Index::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn store_and_read() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let root = TempDir::new();
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to open storage");
+
+    assert!(matches!(
+        storage.store(rev.clone(), "receiver".into()).await,
+        Err(FsStorageError::MissingPrevious { .. })
+    ));
+    storage.store(previous, "sender".into()).await.unwrap();
+    storage.store(rev.clone(), "receiver".into()).await.unwrap();
+    assert!(matches!(
+        storage.store(rev, "receiver".into()).await,
+        Err(FsStorageError::AlreadyStored(_))
+    ));
+    assert!(root.files().revision_path(&hash, JSON_SUFFIX).is_file());
+
+    assert_eq!(storage.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(storage.get_context(hash).await.unwrap(), "receiver");
+    assert_eq!(
+        storage.read(hash).await.unwrap().metadata.verification_hash,
+        hash
+    );
+    assert!(matches!(
+        storage.read(Hash::default()).await,
+        Err(FsStorageError::NotFound(_))
+    ));
+    let branch = storage.get_branch(hash).await.unwrap();
+    assert_eq!(branch.hashes, [previous_hash, hash]);
+    assert_eq!(branch.metadata, "receiver");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reads_start_when_polled() {
+    let previous = crate::tests::fixtures::sender();
+    let root = TempDir::new();
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to open storage");
+
+    // Created before the revision is stored, but only run once awaited.
+    let pending = storage.read(previous.metadata.verification_hash);
+    storage.store(previous, "sender".into()).await.unwrap();
+    assert!(pending.await.is_ok());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reopen() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let root = TempDir::new();
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to open storage");
+    storage.store(previous, "sender".into()).await.unwrap();
+    storage.store(rev, "receiver".into()).await.unwrap();
+    drop(storage);
+
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to reopen storage");
+    assert_eq!(storage.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(storage.genesis_hashes().await.unwrap(), [previous_hash]);
+    assert_eq!(storage.get_context(hash).await.unwrap(), "receiver");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn rebuild_after_crash() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let other = crate::tests::fixtures::sender_no_terms();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let other_hash = other.metadata.verification_hash;
+    let root = TempDir::new();
+    let files = root.files();
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to open storage");
+    storage.store(previous, "sender".into()).await.unwrap();
+    storage.store(rev, "receiver".into()).await.unwrap();
+    drop(storage);
+
+    // A crash after writing a revision file, before updating the branch index.
+    write_json(&files.revision_path(&other_hash, CONTEXT_SUFFIX), "other").unwrap();
+    write_json(&files.revision_path(&other_hash, JSON_SUFFIX), &other).unwrap();
+    assert!(files.load_current_index().unwrap().is_none());
+    std::fs::write(files.branch_path(&previous_hash), "not json").unwrap();
+    let leftover = files.revision_path(&other_hash, TMP_SUFFIX);
+    std::fs::write(&leftover, "partial").unwrap();
+
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to rebuild storage");
+    assert!(!leftover.exists());
+    let mut genesis_hashes = vec![previous_hash, other_hash];
+    genesis_hashes.sort();
+    assert_eq!(storage.genesis_hashes().await.unwrap(), genesis_hashes);
+    assert_eq!(storage.get_context(other_hash).await.unwrap(), "other");
+    assert_eq!(
+        storage.get_branch(hash).await.unwrap().hashes,
+        [previous_hash, hash]
+    );
+    let listed = storage.list().await.unwrap();
+    assert_eq!(listed.len(), 3);
+    let position = |hash| listed.iter().position(|h| *h == hash).unwrap();
+    assert!(position(previous_hash) < position(hash));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn record_orphans() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let previous_hash = previous.metadata.verification_hash;
+    let root = TempDir::new();
+    let files = root.files();
+    let storage = FsStorage::<String>::open(&root.0).expect("failed to open storage");
+    storage.store(previous, "sender".into()).await.unwrap();
+
+    let mut orphan = rev;
+    orphan.metadata.verification_hash = Hash::from([7; 64]);
+    orphan.metadata.previous_verification_hash = Some(Hash::from([8; 64]));
+    write_json(
+        &files.revision_path(&Hash::from([7; 64]), JSON_SUFFIX),
+        &orphan,
+    )
+    .unwrap();
+    assert!(files.load_current_index().unwrap().is_none());
+    assert_eq!(
+        storage.rebuild_index().await.unwrap(),
+        [Hash::from([7; 64])]
+    );
+
+    // The orphan is recorded, so the index is current, but it is neither listed nor read.
+    assert!(files.load_current_index().unwrap().is_some());
+    assert_eq!(storage.list().await.unwrap(), [previous_hash]);
+    assert!(matches!(
+        storage.read(Hash::from([7; 64])).await,
+        Err(FsStorageError::NotFound(_))
+    ));
+
+    // An index listing as many revisions, but not the same ones, is rebuilt.
+    let mut other = orphan;
+    other.metadata.verification_hash = Hash::from([9; 64]);
+    std::fs::remove_file(files.revision_path(&Hash::from([7; 64]), JSON_SUFFIX)).unwrap();
+    write_json(
+        &files.revision_path(&Hash::from([9; 64]), JSON_SUFFIX),
+        &other,
+    )
+    .unwrap();
+    assert!(files.load_current_index().unwrap().is_none());
+}
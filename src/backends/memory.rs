@@ -0,0 +1,354 @@
+//! In-memory [`Storage`], for tests and short-lived runs.
+
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use futures_core::Stream;
+
+use super::events::{self, Subscribers};
+
+use crate::models::branch::Branch;
+use crate::models::hash::Hash;
+use crate::models::revision::Revision;
+use crate::models::storage::{Storage, StorageEvent, UpdateHandlerError};
+
+pub use super::events::{CONTEXT_UPDATED, STORED};
+
+/// A thread-safe [`Storage`] keeping all revisions in memory.
+///
+/// Revisions are keyed by their `verification_hash` and listed in the order they were
+/// stored, so every revision is listed after the revision it follows.
+pub struct MemoryStorage<C> {
+    revisions: RwLock<Revisions<C>>,
+    subscribers: Subscribers,
+}
+
+struct Revisions<C> {
+    by_hash: HashMap<Hash, (Revision, C)>,
+    order: Vec<Hash>,
+}
+
+impl<C> MemoryStorage<C> {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        MemoryStorage {
+            revisions: RwLock::new(Revisions {
+                by_hash: HashMap::new(),
+                order: Vec::new(),
+            }),
+            subscribers: Subscribers::default(),
+        }
+    }
+
+    /// Number of stored revisions.
+    pub fn len(&self) -> usize {
+        self.revisions().order.len()
+    }
+
+    /// Returns `true` if no revision is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes a revision, orphaning the revisions following it, which `store` never does.
+    #[cfg(test)]
+    pub(crate) fn remove(&self, hash: Hash) {
+        let mut revisions = self
+            .revisions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        revisions.by_hash.remove(&hash);
+        revisions.order.retain(|stored| *stored != hash);
+    }
+
+    fn revisions(&self) -> std::sync::RwLockReadGuard<'_, Revisions<C>> {
+        self.revisions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C> Default for MemoryStorage<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clone + Send + Sync> Storage for MemoryStorage<C> {
+    type Error = MemoryStorageError;
+    type Context = C;
+
+    async fn get_context(&self, hash: Hash) -> Result<C, Self::Error> {
+        let revisions = self.revisions();
+        let (_, context) = revisions
+            .by_hash
+            .get(&hash)
+            .ok_or(MemoryStorageError::NotFound(hash))?;
+        Ok(context.clone())
+    }
+
+    async fn store(&self, rev: Revision, context: C) -> Result<(), Self::Error> {
+        let hash = rev.metadata.verification_hash;
+        let previous = rev.metadata.previous_verification_hash;
+        let forked = {
+            let mut revisions = self
+                .revisions
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            if revisions.by_hash.contains_key(&hash) {
+                return Err(MemoryStorageError::AlreadyStored(hash));
+            }
+            if let Some(previous) = previous.filter(|p| !revisions.by_hash.contains_key(p)) {
+                return Err(MemoryStorageError::MissingPrevious { hash, previous });
+            }
+            let forked = previous.is_some_and(|previous| {
+                revisions
+                    .by_hash
+                    .values()
+                    .any(|(stored, _)| stored.metadata.previous_verification_hash == Some(previous))
+            });
+            revisions.by_hash.insert(hash, (rev, context));
+            revisions.order.push(hash);
+            forked
+        };
+        self.subscribers
+            .publish(&events::stored(hash, previous, forked));
+        Ok(())
+    }
+
+    async fn read(&self, hash: Hash) -> Result<Revision, Self::Error> {
+        let revisions = self.revisions();
+        let (rev, _) = revisions
+            .by_hash
+            .get(&hash)
+            .ok_or(MemoryStorageError::NotFound(hash))?;
+        Ok(rev.clone())
+    }
+
+    /// Builds the branch ending in `hash` by following `previous_verification_hash` back
+    /// to the genesis revision. Hashes are ordered genesis first, the context is the one
+    /// stored with `hash`.
+    async fn get_branch(&self, hash: Hash) -> Result<Branch<C>, Self::Error> {
+        let revisions = self.revisions();
+        let (_, context) = revisions
+            .by_hash
+            .get(&hash)
+            .ok_or(MemoryStorageError::NotFound(hash))?;
+        // `store` keeps every previous revision, which is stored before its successors.
+        let mut hashes = vec![hash];
+        let mut current = hash;
+        while let Some(previous) = revisions.by_hash[&current]
+            .0
+            .metadata
+            .previous_verification_hash
+        {
+            hashes.push(previous);
+            current = previous;
+        }
+        hashes.reverse();
+        Ok(Branch {
+            metadata: context.clone(),
+            hashes,
+        })
+    }
+
+    async fn list(&self) -> Result<Vec<Hash>, Self::Error> {
+        Ok(self.revisions().order.clone())
+    }
+
+    fn subscribe(&self) -> impl Stream<Item = StorageEvent> + Send + Unpin + 'static {
+        self.subscribers.subscribe()
+    }
+}
+
+/// Error types of [`MemoryStorage`].
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryStorageError {
+    /// No revision with this `verification_hash` is stored.
+    #[error("revision {0} not found")]
+    NotFound(Hash),
+
+    /// A revision with this `verification_hash` is already stored.
+    #[error("revision {0} is already stored")]
+    AlreadyStored(Hash),
+
+    /// The revision a revision to store follows is not stored.
+    #[error("revision {previous} followed by {hash} not found")]
+    MissingPrevious { hash: Hash, previous: Hash },
+
+    /// The future returned by the deprecated `update_handler` stopped.
+    #[error(transparent)]
+    UpdateHandler(#[from] UpdateHandlerError),
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn store_and_read() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let storage = MemoryStorage::new();
+
+    assert_eq!(
+        storage.store(rev.clone(), "receiver").await,
+        Err(MemoryStorageError::MissingPrevious {
+            hash,
+            previous: previous_hash
+        })
+    );
+    storage.store(previous.clone(), "sender").await.unwrap();
+    assert_eq!(
+        storage.store(previous, "sender").await,
+        Err(MemoryStorageError::AlreadyStored(previous_hash))
+    );
+    storage.store(rev, "receiver").await.unwrap();
+
+    assert_eq!(storage.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(storage.get_context(previous_hash).await.unwrap(), "sender");
+    assert_eq!(
+        storage.read(hash).await.unwrap().metadata.verification_hash,
+        hash
+    );
+    assert_eq!(
+        storage.read(Hash::default()).await.unwrap_err(),
+        MemoryStorageError::NotFound(Hash::default())
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn get_branch() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let storage = MemoryStorage::new();
+    storage.store(previous, "sender").await.unwrap();
+    storage.store(rev, "receiver").await.unwrap();
+
+    let branch = storage.get_branch(hash).await.unwrap();
+    assert_eq!(branch.hashes, [previous_hash, hash]);
+    assert_eq!(branch.metadata, "receiver");
+    assert_eq!(
+        storage.get_branch(previous_hash).await.unwrap().hashes,
+        [previous_hash]
+    );
+    assert_eq!(
+        storage.get_branch(Hash::default()).await.unwrap_err(),
+        MemoryStorageError::NotFound(Hash::default())
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn subscribe_events() {
+    use std::pin::Pin;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let mut sibling = rev.clone();
+    sibling.metadata.verification_hash = Hash::default();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+
+    let storage = MemoryStorage::new();
+    let mut events = storage.subscribe();
+    drop(storage.subscribe());
+    storage.store(previous, ()).await.unwrap();
+    assert_eq!(storage.subscribers.len(), 1);
+    storage.store(rev, ()).await.unwrap();
+    storage.store(sibling, ()).await.unwrap();
+    drop(storage);
+
+    let mut received = Vec::new();
+    while let Some(event) = std::future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+        received.push(event);
+    }
+    assert_eq!(
+        received,
+        [
+            StorageEvent::RevisionStored(previous_hash),
+            StorageEvent::RevisionStored(hash),
+            StorageEvent::BranchExtended {
+                previous: previous_hash,
+                hash
+            },
+            StorageEvent::RevisionStored(Hash::default()),
+            StorageEvent::BranchForked {
+                previous: previous_hash,
+                hash: Hash::default()
+            },
+        ]
+    );
+}
+
+/// Stores `count` genesis revisions with made-up hashes.
+#[cfg(test)]
+async fn store_genesis_revisions(storage: &MemoryStorage<()>, count: usize) {
+    for n in 0..count {
+        let mut rev = Revision::default();
+        rev.metadata.verification_hash = Hash::from([u8::try_from(n).unwrap(); 64]);
+        storage.store(rev, ()).await.unwrap();
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn disconnect_lagging_subscriber() {
+    use std::pin::Pin;
+
+    // A subscriber that is never polled is disconnected instead of blocking `store`.
+    let storage = MemoryStorage::new();
+    let mut lagging = storage.subscribe();
+    store_genesis_revisions(&storage, events::CAPACITY + 2).await;
+    assert_eq!(storage.subscribers.len(), 0);
+
+    let mut received = Vec::new();
+    while let Some(event) = std::future::poll_fn(|cx| Pin::new(&mut lagging).poll_next(cx)).await
+    {
+        received.push(event);
+    }
+    assert_eq!(received.len(), events::CAPACITY + 1);
+    assert_eq!(received.last(), Some(&StorageEvent::Lagged));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn update_handler_adapter() {
+    use std::sync::Mutex;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let storage = MemoryStorage::new();
+    let updates = Mutex::new(Vec::new());
+    #[allow(deprecated)]
+    let handler = storage.update_handler(|hash, description| {
+        updates.lock().unwrap().push((hash, description))
+    });
+    storage.store(previous, ()).await.unwrap();
+    storage.store(rev, ()).await.unwrap();
+
+    // Falling behind by more events than are buffered ends the handler with an error.
+    store_genesis_revisions(&storage, events::CAPACITY).await;
+    assert_eq!(
+        handler.await,
+        Err(MemoryStorageError::UpdateHandler(UpdateHandlerError::Lagged))
+    );
+    let updates = updates.into_inner().unwrap();
+    assert_eq!(
+        updates[..2],
+        [
+            (previous_hash, STORED.to_string()),
+            (hash, STORED.to_string())
+        ]
+    );
+    // Three buffered events were of the first two revisions, one for the branch.
+    assert_eq!(updates.len(), events::CAPACITY - 1);
+}
@@ -0,0 +1,611 @@
+//! [`Storage`] in an SQLite database, enabled with the `sqlite` feature.
+//!
+//! Tables:
+//! - `revisions`: every revision as JSON, next to the columns it is looked up by
+//!   (`genesis_hash`, `domain_id`, `time_stamp` and the signer's `wallet_address`).
+//! - `contexts`: the context stored with each revision, as JSON.
+//! - `branches`: one row per genesis revision, with the number of revisions descending
+//!   from it and the one stored last as `latest_hash`. A revision is only stored after
+//!   the one it follows, so `latest_hash` is always a tip: no stored revision follows it.
+//!   On a forked branch, it is the tip of the fork stored to last.
+//!
+//! The schema version is kept in `PRAGMA user_version`; [`SqliteStorage::open`] applies
+//! the [`MIGRATIONS`] the database has not seen yet.
+//!
+//! The connection is owned by a worker thread, so the futures returned by [`Storage`]
+//! never block the runtime polling them. The worker stops once the storage is dropped.
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use ethaddr::Address;
+use futures_core::Stream;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use super::events::{self, Subscribers};
+use super::worker::Worker;
+
+use crate::error::ReadError;
+use crate::models::branch::Branch;
+use crate::models::hash::Hash;
+use crate::models::revision::Revision;
+use crate::models::storage::{Storage, StorageEvent, UpdateHandlerError};
+
+pub use super::events::{CONTEXT_UPDATED, STORED};
+
+/// Schema migrations, applied in order. Migration `n` brings the database to
+/// `user_version` `n + 1`.
+pub const MIGRATIONS: &[&str] = &[r#"
+CREATE TABLE revisions (
+    verification_hash TEXT PRIMARY KEY NOT NULL,
+    previous_verification_hash TEXT REFERENCES revisions (verification_hash),
+    genesis_hash TEXT NOT NULL,
+    domain_id TEXT NOT NULL,
+    time_stamp TEXT NOT NULL,
+    wallet_address TEXT,
+    revision TEXT NOT NULL
+);
+CREATE INDEX revisions_previous_verification_hash ON revisions (previous_verification_hash);
+CREATE INDEX revisions_domain_id ON revisions (domain_id);
+CREATE INDEX revisions_genesis_hash ON revisions (genesis_hash);
+CREATE INDEX revisions_time_stamp ON revisions (time_stamp);
+CREATE INDEX revisions_wallet_address ON revisions (wallet_address);
+
+CREATE TABLE contexts (
+    verification_hash TEXT PRIMARY KEY NOT NULL REFERENCES revisions (verification_hash),
+    context TEXT NOT NULL
+);
+
+CREATE TABLE branches (
+    genesis_hash TEXT PRIMARY KEY NOT NULL REFERENCES revisions (verification_hash),
+    latest_hash TEXT NOT NULL REFERENCES revisions (verification_hash),
+    revision_count INTEGER NOT NULL
+);
+"#];
+
+/// A [`Storage`] keeping revisions in an SQLite database.
+pub struct SqliteStorage<C> {
+    worker: Worker<Connection>,
+    subscribers: Subscribers,
+    context: PhantomData<fn() -> C>,
+}
+
+impl<C> SqliteStorage<C> {
+    /// Opens the database at `path`, creating it if needed, and migrates it to the
+    /// latest schema.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SqliteStorageError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Opens a new database in memory.
+    pub fn open_in_memory() -> Result<Self, SqliteStorageError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut connection: Connection) -> Result<Self, SqliteStorageError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut connection)?;
+        let worker = Worker::spawn("aqua-sqlite", connection)?;
+        Ok(SqliteStorage {
+            worker,
+            subscribers: Subscribers::default(),
+            context: PhantomData,
+        })
+    }
+
+    /// Hashes of all genesis revisions.
+    pub async fn genesis_hashes(&self) -> Result<Vec<Hash>, SqliteStorageError> {
+        self.run(|connection| {
+            query_hashes(
+                connection,
+                "SELECT genesis_hash FROM branches ORDER BY genesis_hash",
+                [],
+            )
+        })
+        .await
+    }
+
+    /// Hashes of all revisions of `domain_id`, oldest first.
+    pub async fn revisions_in_domain(
+        &self,
+        domain_id: String,
+    ) -> Result<Vec<Hash>, SqliteStorageError> {
+        self.run(move |connection| {
+            query_hashes(
+                connection,
+                "SELECT verification_hash FROM revisions WHERE domain_id = ?1 \
+                 ORDER BY time_stamp, rowid",
+                [domain_id],
+            )
+        })
+        .await
+    }
+
+    /// Hashes of all revisions signed by `wallet_address`, oldest first.
+    pub async fn revisions_signed_by(
+        &self,
+        wallet_address: Address,
+    ) -> Result<Vec<Hash>, SqliteStorageError> {
+        self.run(move |connection| {
+            query_hashes(
+                connection,
+                "SELECT verification_hash FROM revisions WHERE wallet_address = ?1 \
+                 ORDER BY time_stamp, rowid",
+                [address_key(wallet_address)],
+            )
+        })
+        .await
+    }
+
+    /// Runs `f` on the worker thread once the returned future is first polled.
+    fn run<T, F>(&self, f: F) -> impl Future<Output = Result<T, SqliteStorageError>> + Send + Sync
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, SqliteStorageError> + Send + Sync + 'static,
+    {
+        let result = self.worker.run(f);
+        async move {
+            result
+                .await
+                .unwrap_or(Err(SqliteStorageError::WorkerStopped))
+        }
+    }
+}
+
+impl<C> Storage for SqliteStorage<C>
+where
+    C: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+{
+    type Error = SqliteStorageError;
+    type Context = C;
+
+    async fn get_context(&self, hash: Hash) -> Result<C, Self::Error> {
+        self.run(move |connection| read_context(connection, hash))
+            .await
+    }
+
+    /// Stores `rev` in a single transaction. The revision it follows has to be stored
+    /// already.
+    async fn store(&self, rev: Revision, context: C) -> Result<(), Self::Error> {
+        let hash = rev.metadata.verification_hash;
+        let previous = rev.metadata.previous_verification_hash;
+        let context = serde_json::to_string(&context)?;
+        let forked = self
+            .run(move |connection| {
+                let transaction = connection.transaction()?;
+                let forked = insert_revision(&transaction, &rev, &context)?;
+                transaction.commit()?;
+                Ok(forked)
+            })
+            .await?;
+        self.subscribers
+            .publish(&events::stored(hash, previous, forked));
+        Ok(())
+    }
+
+    fn read(
+        &self,
+        hash: Hash,
+    ) -> impl Future<Output = Result<Revision, Self::Error>> + Send + Sync {
+        self.run(move |connection| {
+            let rev: String = connection
+                .query_row(
+                    "SELECT revision FROM revisions WHERE verification_hash = ?1",
+                    [hash.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or(SqliteStorageError::NotFound(hash))?;
+            Ok(serde_json::from_str(&rev)?)
+        })
+    }
+
+    /// Builds the branch ending in `hash` by following `previous_verification_hash` back
+    /// to the genesis revision. Hashes are ordered genesis first, the context is the one
+    /// stored with `hash`.
+    async fn get_branch(&self, hash: Hash) -> Result<Branch<C>, Self::Error> {
+        self.run(move |connection| {
+            let metadata = read_context(connection, hash)?;
+            let hashes = query_hashes(
+                connection,
+                "WITH RECURSIVE chain (verification_hash, previous_verification_hash, depth) AS (
+                     SELECT verification_hash, previous_verification_hash, 0
+                     FROM revisions WHERE verification_hash = ?1
+                     UNION ALL
+                     SELECT revisions.verification_hash, revisions.previous_verification_hash,
+                         chain.depth + 1
+                     FROM revisions JOIN chain
+                         ON revisions.verification_hash = chain.previous_verification_hash
+                 )
+                 SELECT verification_hash FROM chain ORDER BY depth DESC",
+                [hash.to_string()],
+            )?;
+            Ok(Branch { metadata, hashes })
+        })
+        .await
+    }
+
+    /// Lists all revisions in the order they were stored.
+    async fn list(&self) -> Result<Vec<Hash>, Self::Error> {
+        self.run(|connection| {
+            query_hashes(
+                connection,
+                "SELECT verification_hash FROM revisions ORDER BY rowid",
+                [],
+            )
+        })
+        .await
+    }
+
+    fn subscribe(&self) -> impl Stream<Item = StorageEvent> + Send + Unpin + 'static {
+        self.subscribers.subscribe()
+    }
+}
+
+/// Applies the migrations missing from the database, each in its own transaction.
+fn migrate(connection: &mut Connection) -> Result<(), SqliteStorageError> {
+    let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let applied = usize::try_from(version)
+        .ok()
+        .filter(|&applied| applied <= MIGRATIONS.len())
+        .ok_or(SqliteStorageError::UnknownSchema(version))?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+/// Inserts `rev` and its context and returns whether another revision already follows
+/// the revision `rev` follows.
+fn insert_revision(
+    transaction: &Transaction,
+    rev: &Revision,
+    context: &str,
+) -> Result<bool, SqliteStorageError> {
+    let hash = rev.metadata.verification_hash;
+    let exists = transaction
+        .query_row(
+            "SELECT 1 FROM revisions WHERE verification_hash = ?1",
+            [hash.to_string()],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if exists {
+        return Err(SqliteStorageError::AlreadyStored(hash));
+    }
+    let forked = match rev.metadata.previous_verification_hash {
+        None => false,
+        Some(previous) => transaction.query_row(
+            "SELECT EXISTS (SELECT 1 FROM revisions WHERE previous_verification_hash = ?1)",
+            [previous.to_string()],
+            |row| row.get(0),
+        )?,
+    };
+    let genesis = match rev.metadata.previous_verification_hash {
+        None => hash.to_string(),
+        Some(previous) => transaction
+            .query_row(
+                "SELECT genesis_hash FROM revisions WHERE verification_hash = ?1",
+                [previous.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SqliteStorageError::MissingPrevious { previous })?,
+    };
+
+    transaction.execute(
+        "INSERT INTO revisions (verification_hash, previous_verification_hash, genesis_hash,
+             domain_id, time_stamp, wallet_address, revision)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            hash.to_string(),
+            rev.metadata
+                .previous_verification_hash
+                .map(|previous| previous.to_string()),
+            genesis,
+            rev.metadata.domain_id,
+            rev.metadata.time_stamp.to_string(),
+            rev.signature
+                .as_ref()
+                .map(|signature| address_key(signature.wallet_address)),
+            serde_json::to_string(rev)?,
+        ],
+    )?;
+    transaction.execute(
+        "INSERT INTO contexts (verification_hash, context) VALUES (?1, ?2)",
+        params![hash.to_string(), context],
+    )?;
+    transaction.execute(
+        "INSERT INTO branches (genesis_hash, latest_hash, revision_count) VALUES (?1, ?2, 1)
+         ON CONFLICT (genesis_hash) DO UPDATE
+         SET latest_hash = excluded.latest_hash, revision_count = revision_count + 1",
+        params![genesis, hash.to_string()],
+    )?;
+    Ok(forked)
+}
+
+fn read_context<C: serde::de::DeserializeOwned>(
+    connection: &Connection,
+    hash: Hash,
+) -> Result<C, SqliteStorageError> {
+    let context: String = connection
+        .query_row(
+            "SELECT context FROM contexts WHERE verification_hash = ?1",
+            [hash.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(SqliteStorageError::NotFound(hash))?;
+    Ok(serde_json::from_str(&context)?)
+}
+
+fn query_hashes(
+    connection: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Hash>, SqliteStorageError> {
+    let mut statement = connection.prepare_cached(sql)?;
+    let hashes = statement
+        .query_map(params, |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    hashes
+        .iter()
+        .map(|hash| hash.parse().map_err(SqliteStorageError::from))
+        .collect()
+}
+
+/// Wallet addresses are stored as lowercase hex, so lookups do not depend on the
+/// checksum casing.
+fn address_key(address: Address) -> String {
+    format!("{address:#x}")
+}
+
+/// Error types of [`SqliteStorage`].
+#[derive(thiserror::Error, Debug)]
+pub enum SqliteStorageError {
+    /// The database reported an error.
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// A revision or context could not be converted from or to JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// The database holds a hash that cannot be read.
+    #[error("invalid hash in database: {0}")]
+    InvalidHash(#[from] ReadError),
+
+    /// The database was migrated by a newer version; its `user_version` is unknown.
+    #[error("unknown schema version {0}")]
+    UnknownSchema(i64),
+
+    /// The worker thread owning the connection could not be started.
+    #[error("cannot start database worker: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The worker thread owning the connection is gone.
+    #[error("database worker stopped")]
+    WorkerStopped,
+
+    /// No revision with this `verification_hash` is stored.
+    #[error("revision {0} not found")]
+    NotFound(Hash),
+
+    /// A revision with this `verification_hash` is already stored.
+    #[error("revision {0} is already stored")]
+    AlreadyStored(Hash),
+
+    /// The revision a revision follows is not stored.
+    #[error("previous revision {previous} not found")]
+    MissingPrevious { previous: Hash },
+
+    /// The future returned by the deprecated `update_handler` stopped.
+    #[error(transparent)]
+    UpdateHandler(#[from] UpdateHandlerError),
+}
+
+/// A temporary database file, removed when dropped.
+#[cfg(test)]
+struct TempFile(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempFile {
+    fn new() -> Self {
+        TempFile(std::env::temp_dir().join(format!("aqua-sqlite-{:x}.db", rand::random::<u64>())))
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Opens a database in memory holding the [`signed_pair`](crate::tests::fixtures::signed_pair).
+#[cfg(test)]
+async fn signed_pair_storage() -> (SqliteStorage<String>, Revision, Revision) {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let storage = SqliteStorage::open_in_memory().unwrap();
+    storage
+        .store(previous.clone(), "sender".to_string())
+        .await
+        .unwrap();
+    storage
+        .store(rev.clone(), "receiver".to_string())
+        .await
+        .unwrap();
+    (storage, previous, rev)
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn store_and_read() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let storage = SqliteStorage::open_in_memory().unwrap();
+
+    assert!(matches!(
+        storage.store(rev.clone(), "receiver".to_string()).await,
+        Err(SqliteStorageError::MissingPrevious { previous }) if previous == previous_hash
+    ));
+    assert!(storage.list().await.unwrap().is_empty());
+    storage
+        .store(previous.clone(), "sender".to_string())
+        .await
+        .unwrap();
+    storage
+        .store(rev.clone(), "receiver".to_string())
+        .await
+        .unwrap();
+    assert!(matches!(
+        storage.store(previous, "sender".to_string()).await,
+        Err(SqliteStorageError::AlreadyStored(stored)) if stored == previous_hash
+    ));
+
+    assert_eq!(storage.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(storage.get_context(previous_hash).await.unwrap(), "sender");
+    assert_eq!(
+        serde_json::to_value(storage.read(hash).await.unwrap()).unwrap(),
+        serde_json::to_value(&rev).unwrap()
+    );
+    assert!(matches!(
+        storage.read(Hash::default()).await,
+        Err(SqliteStorageError::NotFound(_))
+    ));
+    let branch = storage.get_branch(hash).await.unwrap();
+    assert_eq!(branch.hashes, [previous_hash, hash]);
+    assert_eq!(branch.metadata, "receiver");
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reads_start_when_polled() {
+    let previous = crate::tests::fixtures::sender();
+    let storage = SqliteStorage::open_in_memory().unwrap();
+
+    // Created before the revision is stored, but only run once awaited.
+    let pending = storage.read(previous.metadata.verification_hash);
+    storage.store(previous, "sender".to_string()).await.unwrap();
+    assert!(pending.await.is_ok());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn query_columns() {
+    let (storage, previous, rev) = signed_pair_storage().await;
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+
+    assert_eq!(storage.genesis_hashes().await.unwrap(), [previous_hash]);
+    assert_eq!(
+        storage
+            .revisions_in_domain(rev.metadata.domain_id.clone())
+            .await
+            .unwrap(),
+        [previous_hash, hash]
+    );
+    let signer = rev.signature.as_ref().unwrap().wallet_address;
+    assert_eq!(storage.revisions_signed_by(signer).await.unwrap(), [hash]);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn latest_hash_is_a_tip() {
+    let (storage, previous, rev) = signed_pair_storage().await;
+    let previous_hash = previous.metadata.verification_hash;
+    let mut sibling = rev;
+    sibling.metadata.verification_hash = Hash::default();
+    storage.store(sibling, "sibling".to_string()).await.unwrap();
+
+    let branch = storage
+        .run(move |connection| {
+            Ok(connection.query_row(
+                "SELECT latest_hash, revision_count,
+                     EXISTS (SELECT 1 FROM revisions
+                         WHERE previous_verification_hash = branches.latest_hash)
+                 FROM branches WHERE genesis_hash = ?1",
+                [previous_hash.to_string()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, bool>(2)?,
+                    ))
+                },
+            )?)
+        })
+        .await
+        .unwrap();
+    assert_eq!(branch, (Hash::default().to_string(), 3, false));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn subscribe_events() {
+    let (storage, previous, rev) = signed_pair_storage().await;
+    let previous_hash = previous.metadata.verification_hash;
+
+    let mut events = storage.subscribe();
+    let mut sibling = rev;
+    sibling.metadata.verification_hash = Hash::default();
+    storage.store(sibling, "sibling".to_string()).await.unwrap();
+    drop(storage);
+    let mut received = Vec::new();
+    while let Some(event) =
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut events).poll_next(cx)).await
+    {
+        received.push(event);
+    }
+    assert_eq!(
+        received,
+        [
+            StorageEvent::RevisionStored(Hash::default()),
+            StorageEvent::BranchForked {
+                previous: previous_hash,
+                hash: Hash::default()
+            },
+        ]
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reopen_and_migrate() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+    let file = TempFile::new();
+    let storage = SqliteStorage::open(&file.0).unwrap();
+    storage.store(previous, "sender".to_string()).await.unwrap();
+    storage.store(rev, "receiver".to_string()).await.unwrap();
+    drop(storage);
+
+    let storage = SqliteStorage::<String>::open(&file.0).unwrap();
+    assert_eq!(storage.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(storage.get_context(hash).await.unwrap(), "receiver");
+    drop(storage);
+
+    // A database migrated by a newer version is not opened.
+    let connection = Connection::open(&file.0).unwrap();
+    connection
+        .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+        .unwrap();
+    drop(connection);
+    assert!(matches!(
+        SqliteStorage::<String>::open(&file.0),
+        Err(SqliteStorageError::UnknownSchema(_))
+    ));
+}
@@ -0,0 +1,49 @@
+//! A thread owning the state of a storage backend, so blocking IO never runs on the
+//! runtime polling the storage's futures.
+
+use std::future::Future;
+use std::sync::mpsc;
+
+use futures_channel::oneshot;
+
+type Job<S> = Box<dyn FnOnce(&mut S) + Send>;
+
+/// Runs jobs on a state of type `S`, one at a time, on its own thread. The thread stops
+/// once the worker is dropped and the queued jobs are done.
+pub(crate) struct Worker<S> {
+    jobs: mpsc::Sender<Job<S>>,
+}
+
+impl<S: Send + 'static> Worker<S> {
+    /// Moves `state` to a new thread called `name`.
+    pub(crate) fn spawn(name: &str, mut state: S) -> std::io::Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job<S>>();
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                for job in queue {
+                    job(&mut state);
+                }
+            })?;
+        Ok(Worker { jobs })
+    }
+
+    /// Runs `f` on the worker thread once the returned future is first polled.
+    ///
+    /// The future resolves to `None` if the thread stopped before `f` returned.
+    pub(crate) fn run<T, F>(&self, f: F) -> impl Future<Output = Option<T>> + Send + Sync
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut S) -> T + Send + Sync + 'static,
+    {
+        let jobs = self.jobs.clone();
+        async move {
+            let (sender, receiver) = oneshot::channel();
+            let job: Job<S> = Box::new(move |state| {
+                let _ = sender.send(f(state));
+            });
+            jobs.send(job).ok()?;
+            receiver.await.ok()
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Data Access Agreements between two parties.
+//!
+//! An agreement is a page whose `main` slot holds the template
+//! `{{DataAccessAgreement|sender=…|receiver=…|pages=…|terms=…}}`. It becomes effective
+//! once the sender and then the receiver signed a revision carrying the same agreement.
+
+use ethaddr::Address;
+
+use crate::models::revision::Revision;
+use crate::models::signature::SignatureError;
+
+const TEMPLATE_START: &str = "{{DataAccessAgreement";
+const TEMPLATE_END: &str = "}}";
+
+/// The parameters of a `DataAccessAgreement` template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contract {
+    /// Wallet address of the party sharing the pages.
+    pub sender: Address,
+    /// Wallet address of the party receiving the pages.
+    pub receiver: Address,
+    /// The shared pages, as written in the template.
+    pub pages: String,
+    /// Terms of the agreement, if any.
+    pub terms: Option<String>,
+}
+
+/// Who signed a revision carrying a [`Contract`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigningState {
+    /// The revision is not signed.
+    Unsigned,
+    /// The revision is signed by the sender.
+    Sender,
+    /// The revision is signed by the receiver.
+    Receiver,
+    /// The revision is signed by someone not party to the agreement.
+    Other(Address),
+    /// The signature of the revision is not valid.
+    InvalidSignature,
+}
+
+/// A revision of any protocol version, as far as agreements are concerned.
+pub trait AgreementRevision {
+    /// The `main` content slot, if the revision has one.
+    fn main(&self) -> Option<&str>;
+
+    /// The wallet address that signed the revision.
+    ///
+    /// # Returns
+    /// `None` if the revision is not signed, or an error if the signature does not
+    /// verify over its `verification_hash`.
+    fn signer(&self) -> Option<Result<Address, SignatureError>>;
+}
+
+impl AgreementRevision for Revision {
+    fn main(&self) -> Option<&str> {
+        self.content.content.main()
+    }
+
+    fn signer(&self) -> Option<Result<Address, SignatureError>> {
+        let signature = self.signature.as_ref()?;
+        Some(
+            signature
+                .verify_signer(&self.metadata.verification_hash)
+                .map(|()| signature.wallet_address),
+        )
+    }
+}
+
+impl Contract {
+    /// Reads the agreement from the `main` slot of `rev`.
+    ///
+    /// # Returns
+    /// `None` if the revision carries no `DataAccessAgreement` template.
+    pub fn from_revision(rev: &impl AgreementRevision) -> Option<Result<Self, ContractError>> {
+        let main = rev.main()?;
+        let start = main.find(TEMPLATE_START)?;
+        Some(main[start + TEMPLATE_START.len()..].parse())
+    }
+
+    /// Determines who signed `rev`, relative to this agreement.
+    ///
+    /// Only signatures that verify over the `verification_hash` of `rev` are attributed.
+    pub fn signing_state(&self, rev: &impl AgreementRevision) -> SigningState {
+        match rev.signer() {
+            None => SigningState::Unsigned,
+            Some(Err(_)) => SigningState::InvalidSignature,
+            Some(Ok(signer)) if signer == self.sender => SigningState::Sender,
+            Some(Ok(signer)) if signer == self.receiver => SigningState::Receiver,
+            Some(Ok(signer)) => SigningState::Other(signer),
+        }
+    }
+}
+
+impl std::str::FromStr for Contract {
+    type Err = ContractError;
+
+    /// Parses the parameters of a template, starting right after `{{DataAccessAgreement`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let end = s.find(TEMPLATE_END).ok_or(ContractError::Unterminated)?;
+        let mut params = s[..end].split('|');
+        if !params.next().unwrap_or_default().trim().is_empty() {
+            return Err(ContractError::NotATemplate);
+        }
+
+        let (mut sender, mut receiver, mut pages, mut terms) = (None, None, None, None);
+        for param in params {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| ContractError::InvalidParameter(param.trim().to_string()))?;
+            let value = value.trim();
+            let slot = match key.trim() {
+                "sender" => &mut sender,
+                "receiver" => &mut receiver,
+                "pages" => &mut pages,
+                "terms" => &mut terms,
+                key => return Err(ContractError::UnknownParameter(key.to_string())),
+            };
+            *slot = Some(value);
+        }
+
+        let address = |name: &'static str, value: Option<&str>| {
+            let value = value.ok_or(ContractError::MissingParameter(name))?;
+            value.parse().map_err(|_| ContractError::InvalidAddress {
+                name,
+                value: value.to_string(),
+            })
+        };
+        Ok(Contract {
+            sender: address("sender", sender)?,
+            receiver: address("receiver", receiver)?,
+            pages: pages
+                .ok_or(ContractError::MissingParameter("pages"))?
+                .to_string(),
+            terms: terms.map(str::to_string),
+        })
+    }
+}
+
+/// Decides whether an agreement is in effect.
+///
+/// # Parameters
+/// - `revisions`: The agreements and signing states of the revisions of a page,
+///   latest revision first.
+///
+/// # Returns
+/// The agreement of the latest revision, if its sender and then its receiver signed
+/// revisions carrying it. A changed agreement has to be signed again.
+pub fn is_contract_effective<'a>(
+    revisions: impl IntoIterator<Item = (&'a Contract, SigningState)>,
+) -> Option<&'a Contract> {
+    let revisions: Vec<_> = revisions.into_iter().collect();
+    let mut current: Option<&Contract> = None;
+    let (mut sender_signed, mut effective) = (false, false);
+    for (contract, state) in revisions.into_iter().rev() {
+        if current != Some(contract) {
+            current = Some(contract);
+            (sender_signed, effective) = (false, false);
+        }
+        match state {
+            SigningState::Sender => sender_signed = true,
+            SigningState::Receiver if sender_signed => effective = true,
+            _ => {}
+        }
+    }
+    current.filter(|_| effective)
+}
+
+/// Error types for reading a `DataAccessAgreement` template.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContractError {
+    /// The template name is followed by something other than parameters.
+    #[error("not a DataAccessAgreement template")]
+    NotATemplate,
+
+    /// The template is not closed with `}}`.
+    #[error("DataAccessAgreement template is not terminated")]
+    Unterminated,
+
+    /// A parameter is not written as `key=value`.
+    #[error("invalid parameter `{0}`")]
+    InvalidParameter(String),
+
+    /// The template has a parameter agreements do not know.
+    #[error("unknown parameter `{0}`")]
+    UnknownParameter(String),
+
+    /// A required parameter is missing.
+    #[error("missing parameter `{0}`")]
+    MissingParameter(&'static str),
+
+    /// A party is not a valid wallet address.
+    #[error("`{name}` is not a valid address: {value}")]
+    InvalidAddress { name: &'static str, value: String },
+}
+
+#[test]
+fn read_contract() {
+    let rev = crate::tests::fixtures::sender_no_terms();
+    let contract = Contract::from_revision(&rev)
+        .expect("fixture has no agreement")
+        .expect("failed to read agreement");
+    assert_eq!(contract.pages, "Test page to be shared");
+    assert_eq!(contract.terms, None);
+    assert_eq!(contract.signing_state(&rev), SigningState::Sender);
+
+    assert_eq!(
+        "|sender=0x95b4b2e6d579eb9D8c32B34f8ca6ab11a3849c06|pages=a}}".parse::<Contract>(),
+        Err(ContractError::MissingParameter("receiver"))
+    );
+    assert_eq!(
+        "|sender=nobody|receiver=nobody|pages=a}}".parse::<Contract>(),
+        Err(ContractError::InvalidAddress {
+            name: "sender",
+            value: "nobody".to_string()
+        })
+    );
+    assert_eq!(
+        "|sender=0x95b4b2e6d579eb9D8c32B34f8ca6ab11a3849c06".parse::<Contract>(),
+        Err(ContractError::Unterminated)
+    );
+    assert!(Contract::from_revision(&Revision::default()).is_none());
+}
+
+#[test]
+fn agreement_in_effect() {
+    use crate::tests::fixtures;
+
+    let revisions = [
+        fixtures::sender(),
+        fixtures::receiver(),
+        fixtures::receiver_2(),
+    ];
+    let states: Vec<_> = revisions
+        .iter()
+        .map(|rev| {
+            let contract = Contract::from_revision(rev).unwrap().unwrap();
+            let state = contract.signing_state(rev);
+            (contract, state)
+        })
+        .collect();
+    assert_eq!(
+        states.iter().map(|(_, state)| *state).collect::<Vec<_>>(),
+        [
+            SigningState::Sender,
+            SigningState::Receiver,
+            SigningState::Receiver
+        ]
+    );
+    let effective = |states: &[(Contract, SigningState)]| {
+        is_contract_effective(states.iter().map(|(contract, state)| (contract, *state))).cloned()
+    };
+
+    // Latest revision first: the sender signed before the receiver.
+    let latest_first: Vec<_> = states.iter().rev().cloned().collect();
+    assert_eq!(effective(&latest_first), Some(states[2].0.clone()));
+    // The receiver signed before the sender.
+    assert_eq!(effective(&states), None);
+    // Only the sender signed.
+    assert_eq!(effective(&states[..1]), None);
+}
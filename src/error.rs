@@ -0,0 +1,100 @@
+//! Error types shared across the crate.
+//!
+//! [`ReadError`] is returned when parsing the string forms of hashes, keys, signatures and
+//! encoded data.
+
+/// Error types for parsing the string form of a value.
+///
+/// Offsets are byte offsets into the parsed string, including any `0x` prefix.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    /// The value has the wrong number of hex digits.
+    #[error("expected {expected} hex digits, found {found}")]
+    WrongLength { expected: usize, found: usize },
+
+    /// The value contains a character that is not a hex digit.
+    #[error("invalid hex digit {character:?} at offset {offset}")]
+    NotHex { character: char, offset: usize },
+
+    /// The value is missing the required `0x` prefix.
+    #[error("missing `0x` prefix")]
+    NoPrefix,
+
+    /// The value has a `0x` prefix, but is written without one.
+    #[error("unexpected `0x` prefix")]
+    UnexpectedPrefix,
+
+    /// The value contains an uppercase character, but is written in lowercase.
+    #[error("uppercase character at offset {offset}")]
+    NotLowercase { offset: usize },
+
+    /// The bytes are not a point on the secp256k1 curve.
+    #[error("invalid curve point: {0}")]
+    InvalidCurvePoint(libsecp256k1::Error),
+
+    /// The bytes are not a valid secp256k1 signature with recovery id.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(libsecp256k1::Error),
+
+    /// The value is not valid standard base64.
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+}
+
+/// Decodes `SIZE` bytes from the hex digits `s`, found at `offset` of the parsed string.
+pub(crate) fn read_hex<const SIZE: usize>(s: &str, offset: usize) -> Result<[u8; SIZE], ReadError> {
+    if let Some((index, character)) = s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(ReadError::NotHex {
+            character,
+            offset: offset + index,
+        });
+    }
+    if let Some(index) = s.find(|c: char| c.is_ascii_uppercase()) {
+        return Err(ReadError::NotLowercase {
+            offset: offset + index,
+        });
+    }
+    let mut data = [0u8; SIZE];
+    hex::decode_to_slice(s, &mut data).map_err(|_| ReadError::WrongLength {
+        expected: SIZE * 2,
+        found: s.len(),
+    })?;
+    Ok(data)
+}
+
+/// Decodes `SIZE` bytes from lowercase hex digits with a `0x` prefix.
+pub(crate) fn read_prefixed_hex<const SIZE: usize>(s: &str) -> Result<[u8; SIZE], ReadError> {
+    let digits = s.strip_prefix("0x").ok_or(ReadError::NoPrefix)?;
+    read_hex(digits, 2)
+}
+
+#[test]
+fn read_hex_errors() {
+    assert_eq!(read_hex::<2>("beef", 0), Ok([0xbe, 0xef]));
+    assert_eq!(
+        read_hex::<2>("bee", 0),
+        Err(ReadError::WrongLength {
+            expected: 4,
+            found: 3
+        })
+    );
+    assert_eq!(
+        read_hex::<2>("beefbeef", 0),
+        Err(ReadError::WrongLength {
+            expected: 4,
+            found: 8
+        })
+    );
+    assert_eq!(
+        read_prefixed_hex::<2>("0xbeeg"),
+        Err(ReadError::NotHex {
+            character: 'g',
+            offset: 5
+        })
+    );
+    assert_eq!(
+        read_prefixed_hex::<2>("0xbEef"),
+        Err(ReadError::NotLowercase { offset: 3 })
+    );
+    assert_eq!(read_prefixed_hex::<2>("beef"), Err(ReadError::NoPrefix));
+}
@@ -10,6 +10,7 @@
 //! - `metadata`
 //! - `signature`
 //! - `witness`
+//! - `witness_network`
 //! - `base64`
 //! - `stack_str`
 //! - `timestamp`
@@ -19,6 +20,40 @@
 //! - `revision`
 //! - `storage`
 //! - `branch`
+//! - `transclusion`
+//! - `signature_slot`
+//!
+//! ## Backends
+//!
+//! The `backends` module implements `models::storage::Storage`; `memory` keeps revisions
+//! in memory and `fs` keeps them as JSON files. With the `sqlite` feature, `sqlite` keeps
+//! them in an SQLite database.
+//!
+//! ## Errors
+//!
+//! The `error` module holds `ReadError`, returned when parsing hashes, keys, signatures
+//! and encoded data.
+//!
+//! ## Verification
+//!
+//! The `verify` module checks revisions against the Aqua protocol rules,
+//! the `signer` module creates revision signatures and the `witness_checker` module
+//! looks up witness transactions on Ethereum networks.
+//!
+//! ## Contracts
+//!
+//! The `contracts` module reads Data Access Agreements from revisions and decides
+//! whether they are in effect.
+//!
+//! ## Versions
+//!
+//! The `versions` module holds the revision formats of each protocol version and
+//! converts revisions between them.
+//!
+//! ## Replication
+//!
+//! The `replication` module copies the revisions one storage is missing from another,
+//! parents first, and reports branches that diverged between them.
 
 /// Models for working with various data types and functionalities.
 pub mod models {
@@ -27,6 +62,7 @@ pub mod models {
     pub mod metadata;
     pub mod signature;
     pub mod witness;
+    pub mod witness_network;
     pub mod base64;
     pub mod stack_str;
     pub mod timestamp;
@@ -36,12 +72,51 @@ pub mod models {
     pub mod revision;
     pub mod storage;
     pub mod branch;
+    pub mod transclusion;
+    pub mod signature_slot;
 
     /// Internal tests for the `models` module.
     #[doc(hidden)]
     pub mod tests;
 }
 
+/// Implementations of the `Storage` trait.
+pub mod backends {
+    mod events;
+    mod worker;
+    pub mod fs;
+    pub mod memory;
+    #[cfg(feature = "sqlite")]
+    pub mod sqlite;
+}
+
+/// Error types shared across the crate.
+pub mod error;
+
+/// Verification of revisions.
+pub mod verify;
+
+/// Signing of revisions.
+pub mod signer;
+
+/// Lookup of witness transactions on Ethereum networks.
+pub mod witness_checker;
+
+/// Data Access Agreements between two parties.
+pub mod contracts;
+
+/// Revision formats of the different protocol versions.
+pub mod versions;
+
+/// Replication of revisions between storages.
+pub mod replication;
+
+#[cfg(test)]
+mod tests {
+    pub(crate) mod fixtures;
+    mod revision_test;
+}
+
 /// Cryptography utilities for hashing and digesting data.
 ///
 /// **Note**: This module is hidden from the documentation as it is not part of the primary API.
@@ -65,19 +65,15 @@ impl std::fmt::Display for Base64 {
 }
 
 impl std::str::FromStr for Base64 {
-    //todo: err
-    type Err = ();
+    type Err = crate::error::ReadError;
 
     /// Attempts to decode a base64 string into bytes
     /// 
     /// # Errors
-    /// Returns `Err(())` if the input is not a valid base64
+    /// Returns [`ReadError::Base64`](crate::error::ReadError::Base64) if the input is not
+    /// valid base64, with the offset of the first invalid byte where applicable.
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        let Ok(vec) = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, value)
-        else {
-            return Err(());
-        };
-
+        let vec = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, value)?;
         Ok(Base64::from(vec))
     }
 }
@@ -90,7 +86,7 @@ impl<'de> serde::Deserialize<'de> for Base64 {
     {
         let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
         s.parse()
-            .map_err(|_| serde::de::Error::custom("Invalid Base64"))
+            .map_err(|e| serde::de::Error::custom(format!("Invalid Base64: {e}")))
     }
 }
 
@@ -112,8 +108,12 @@ fn read_base64() {
     //dbg!(_base64_str);
     const TEST_DATA_WITH_WHITESPACE: &str =
         "TmV2 ZXIg Z29u bmEg Z2l2 ZSB5 b3Ug dXAs bmV2 ZXIg Z29u bmEg bGV0 IHlv dSBk b3du IQ==";
-    <Base64 as std::str::FromStr>::from_str(TEST_DATA_WITH_WHITESPACE)
+    let err = <Base64 as std::str::FromStr>::from_str(TEST_DATA_WITH_WHITESPACE)
         .expect_err("Whitespace was wrongfully accepted.");
+    assert_eq!(
+        err,
+        crate::error::ReadError::Base64(base64::DecodeError::InvalidByte(4, b' '))
+    );
 }
 
 #[test]
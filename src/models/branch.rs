@@ -1,6 +1,7 @@
 //! # Branch - revisions with the same `genesis_hash`
 
 use crate::models::hash::Hash;
+use crate::models::revision::Revision;
 
 /// Represents a branch - revisions with the same `genesis_hash`
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -8,3 +9,89 @@ pub struct Branch<T> {
     pub metadata: T,
     pub hashes: Vec<Hash>,
 }
+
+impl<T> Branch<T> {
+    /// The latest revision of the branch, `None` if it is empty.
+    pub fn tip(&self) -> Option<Hash> {
+        self.hashes.last().copied()
+    }
+
+    /// Links `other` into this branch through the merge revision `rev`.
+    ///
+    /// `rev` has to follow the tip of this branch and its `merge_hash` has to be the tip of
+    /// `other`. Revisions of `other` not yet part of this branch are appended (keeping their
+    /// order) before `rev`, which becomes the new tip.
+    pub fn merge<U>(&mut self, other: &Branch<U>, rev: &Revision) -> Result<(), MergeError> {
+        let metadata = &rev.metadata;
+        let merge_hash = metadata.merge_hash.ok_or(MergeError::NotAMerge)?;
+        if metadata.previous_verification_hash != self.tip() {
+            return Err(MergeError::NotFollowingTip {
+                previous: metadata.previous_verification_hash,
+            });
+        }
+        if other.tip() != Some(merge_hash) {
+            return Err(MergeError::NotMergingTip { merge_hash });
+        }
+        let missing: Vec<Hash> = other
+            .hashes
+            .iter()
+            .filter(|hash| !self.hashes.contains(hash))
+            .copied()
+            .collect();
+        self.hashes.extend(missing);
+        self.hashes.push(metadata.verification_hash);
+        Ok(())
+    }
+}
+
+/// Error returned by [`Branch::merge`].
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// The revision has no `merge_hash`.
+    #[error("revision has no merge_hash")]
+    NotAMerge,
+
+    /// The revision does not follow the tip of the branch merged into.
+    #[error("previous_verification_hash {previous:?} is not the branch tip")]
+    NotFollowingTip { previous: Option<Hash> },
+
+    /// The `merge_hash` of the revision is not the tip of the merged branch.
+    #[error("merge_hash {merge_hash} is not the tip of the merged branch")]
+    NotMergingTip { merge_hash: Hash },
+}
+
+#[test]
+fn merge_branches() {
+    let hash = |n: u8| Hash::from([n; 64]);
+    let mut main = Branch {
+        metadata: (),
+        hashes: vec![hash(1), hash(2)],
+    };
+    let fork = Branch {
+        metadata: (),
+        hashes: vec![hash(1), hash(3), hash(4)],
+    };
+
+    let mut rev = Revision::default();
+    rev.metadata.previous_verification_hash = Some(hash(2));
+    rev.metadata.verification_hash = hash(5);
+    assert_eq!(main.merge(&fork, &rev), Err(MergeError::NotAMerge));
+
+    rev.metadata.merge_hash = Some(hash(3));
+    assert!(matches!(
+        main.merge(&fork, &rev),
+        Err(MergeError::NotMergingTip { .. })
+    ));
+
+    rev.metadata.merge_hash = Some(hash(4));
+    rev.metadata.previous_verification_hash = Some(hash(1));
+    assert!(matches!(
+        main.merge(&fork, &rev),
+        Err(MergeError::NotFollowingTip { .. })
+    ));
+
+    rev.metadata.previous_verification_hash = Some(hash(2));
+    main.merge(&fork, &rev).expect("merge failed");
+    assert_eq!(main.hashes, [1, 2, 3, 4, 5].map(hash));
+    assert_eq!(main.tip(), Some(hash(5)));
+}
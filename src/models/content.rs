@@ -1,8 +1,11 @@
 //! This module defines structures related to file content, revisions, signatures, and witness inputs.
 
-// use std::collections::BTreeMap;
+use sha3::Digest;
+
 use crate::models::base64::Base64;
-use crate::models::hash::Hash;
+use crate::models::hash::{Hash, HashMismatch};
+use crate::models::signature_slot::SignatureSlot;
+use crate::models::transclusion::Transclusion;
 
 /// Input data for a revision during the witness operation.
 /// This includes information about the file, transaction, and wallet involved.
@@ -40,18 +43,192 @@ pub struct RevisionContent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file: Option<FileContent>,
     /// (key, value) map for the content `revision` -> `content`->`content` in JSON file.\
-    /// Keys (i.e. `main`, `transclusion_hashes`) need to be sorted, thus using a [`BTreeMap`]
-    pub content: RevisionContentContent, // BTreeMap<String, String>,
+    /// Keys (i.e. `main`, `transclusion-hashes`) keep the order they were written in,
+    /// as the `content_hash` depends on it.
+    pub content: RevisionContentContent,
     /// Value of `content_hash` key of a revision in JSON file
     pub content_hash: Hash,
 }
 
+impl RevisionContent {
+    /// Computes the `content_hash` of the revision.
+    ///
+    /// The hash is the SHA3-512 digest of all content slot values concatenated
+    /// in the order they appear in the revision.
+    pub fn compute_content_hash(&self) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        for slot in self.content.0.iter() {
+            hasher.update(slot.value().as_bytes());
+        }
+        Hash::from(hasher.finalize())
+    }
 
-/// A structured representation of revision content data.
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default)]
-pub struct RevisionContentContent {
-    /// Hash of the file associated with the revision.
-    pub file_hash: Hash,
+    /// Checks the stored `content_hash` (and the `file_hash` slot, if a file is attached)
+    /// against the hashes recomputed from the revision content.
+    ///
+    /// # Errors
+    /// - [`ContentHashError::MissingFileHash`] if a file is attached but no `file_hash` slot exists.
+    /// - [`ContentHashError::FileHashMismatch`] if the file data does not hash to the `file_hash` slot.
+    /// - [`ContentHashError::ContentHashMismatch`] if the slots do not hash to `content_hash`.
+    pub fn verify_content_hash(&self) -> Result<(), ContentHashError> {
+        if let Some(file) = &self.file {
+            let expected = self.content.file_hash().ok_or(ContentHashError::MissingFileHash)?;
+            let computed = file.compute_file_hash();
+            if expected != computed {
+                return Err(ContentHashError::FileHashMismatch(Box::new(HashMismatch {
+                    expected,
+                    computed,
+                })));
+            }
+        }
+        let computed = self.compute_content_hash();
+        if self.content_hash != computed {
+            return Err(ContentHashError::ContentHashMismatch(Box::new(HashMismatch {
+                expected: self.content_hash,
+                computed,
+            })));
+        }
+        Ok(())
+    }
+}
+
+
+/// The content slots of a revision, kept in the order they appear in the JSON file.
+///
+/// Known slots are typed, unknown slots are kept as raw strings (see [`ContentSlot`]).
+/// Serializing writes the slots back in the same order with the same text, so the
+/// `content_hash` stays reproducible.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RevisionContentContent(pub Vec<ContentSlot>);
+
+impl RevisionContentContent {
+    /// Returns the slot named `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&ContentSlot> {
+        self.0.iter().find(|slot| slot.key() == key)
+    }
+
+    /// Text of the page, read from the `main` slot.
+    pub fn main(&self) -> Option<&str> {
+        self.0.iter().find_map(|slot| match slot {
+            ContentSlot::Main(main) => Some(main.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Hash of the file associated with the revision, read from the `file_hash` slot.
+    pub fn file_hash(&self) -> Option<Hash> {
+        self.0.iter().find_map(|slot| match slot {
+            ContentSlot::FileHash(hash) => Some(*hash),
+            _ => None,
+        })
+    }
+
+    /// Raw JSON of the `signature-slot` slot.
+    pub fn signature_slot(&self) -> Option<&str> {
+        self.0.iter().find_map(|slot| match slot {
+            ContentSlot::SignatureSlot(raw) => Some(raw.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Raw JSON of the `transclusion-hashes` slot.
+    pub fn transclusion_hashes(&self) -> Option<&str> {
+        self.0.iter().find_map(|slot| match slot {
+            ContentSlot::TransclusionHashes(raw) => Some(raw.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Recorded signatures, empty if there is no `signature-slot` slot.
+    pub fn signature_slots(&self) -> Result<Vec<SignatureSlot>, serde_json::Error> {
+        self.signature_slot()
+            .map_or(Ok(Vec::new()), super::signature_slot::parse_signature_slots)
+    }
+
+    /// Pages transcluded into the revision, empty if there is no `transclusion-hashes` slot.
+    pub fn transclusions(&self) -> Result<Vec<Transclusion>, serde_json::Error> {
+        self.transclusion_hashes()
+            .map_or(Ok(Vec::new()), super::transclusion::parse_transclusions)
+    }
+}
+
+/// A single content slot of a revision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContentSlot {
+    /// `main`: the text of the page.
+    Main(String),
+    /// `file_hash`: the SHA3-512 digest of the attached file.
+    FileHash(Hash),
+    /// `signature-slot`: JSON list of the expected signers.
+    SignatureSlot(String),
+    /// `transclusion-hashes`: JSON list of the pages transcluded into `main`.
+    TransclusionHashes(String),
+    /// Any other slot, as (key, value).
+    Other(String, String),
+}
+
+impl ContentSlot {
+    /// Classifies a raw (key, value) pair.
+    ///
+    /// A `file_hash` that is not written as a lowercase hex digest is kept as
+    /// [`ContentSlot::Other`], as it could not be written back unchanged otherwise.
+    pub fn new(key: String, value: String) -> Self {
+        match key.as_str() {
+            "main" => ContentSlot::Main(value),
+            "file_hash" => match value.parse::<Hash>() {
+                Ok(hash) if hash.to_string() == value => ContentSlot::FileHash(hash),
+                _ => ContentSlot::Other(key, value),
+            },
+            "signature-slot" => ContentSlot::SignatureSlot(value),
+            "transclusion-hashes" => ContentSlot::TransclusionHashes(value),
+            _ => ContentSlot::Other(key, value),
+        }
+    }
+
+    /// Name of the slot as written in the JSON file.
+    pub fn key(&self) -> &str {
+        match self {
+            ContentSlot::Main(_) => "main",
+            ContentSlot::FileHash(_) => "file_hash",
+            ContentSlot::SignatureSlot(_) => "signature-slot",
+            ContentSlot::TransclusionHashes(_) => "transclusion-hashes",
+            ContentSlot::Other(key, _) => key,
+        }
+    }
+
+    /// Value of the slot as written in the JSON file.
+    pub fn value(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            ContentSlot::Main(value)
+            | ContentSlot::SignatureSlot(value)
+            | ContentSlot::TransclusionHashes(value)
+            | ContentSlot::Other(_, value) => value.into(),
+            ContentSlot::FileHash(hash) => hash.to_string().into(),
+        }
+    }
+}
+
+impl serde::Serialize for RevisionContentContent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for slot in &self.0 {
+            map.serialize_entry(slot.key(), &slot.value())?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for RevisionContentContent {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let slots: Vec<(String, String)> = tuple_vec_map::deserialize(deserializer)?;
+        Ok(RevisionContentContent(
+            slots
+                .into_iter()
+                .map(|(key, value)| ContentSlot::new(key, value))
+                .collect(),
+        ))
+    }
 }
 
 
@@ -67,3 +244,90 @@ pub struct FileContent {
     /// Optional comment associated with the file content.
     pub comment: String,
 }
+
+impl FileContent {
+    /// Computes the SHA3-512 digest of the decoded file data.
+    pub fn compute_file_hash(&self) -> Hash {
+        Hash::from(crate::crypt::Hasher::digest(&self.data[..]))
+    }
+}
+
+/// Error types for checking the `content_hash` of a revision.
+#[derive(thiserror::Error, Debug)]
+pub enum ContentHashError {
+    /// A file is attached, but the content has no `file_hash` slot.
+    #[error("file attached but no valid `file_hash` in content")]
+    MissingFileHash,
+
+    /// The file data does not hash to the `file_hash` slot.
+    #[error("file hash mismatch: {0}")]
+    FileHashMismatch(Box<HashMismatch>),
+
+    /// The content slots do not hash to the stored `content_hash`.
+    #[error("content hash mismatch: {0}")]
+    ContentHashMismatch(Box<HashMismatch>),
+}
+
+#[test]
+fn content_hash_of_signed_revision() {
+    let rev = crate::tests::fixtures::receiver();
+    rev.content.verify_content_hash().expect("content hash of fixture rejected");
+
+    let mut tampered = rev.content.clone();
+    let ContentSlot::Main(main) = &mut tampered.content.0[0] else {
+        panic!("first slot of fixture is not main");
+    };
+    main.push(' ');
+    assert!(matches!(
+        tampered.verify_content_hash(),
+        Err(ContentHashError::ContentHashMismatch(_))
+    ));
+}
+
+#[test]
+fn content_hash_with_file() {
+    let file = FileContent {
+        data: b"Never gonna give you up".to_vec().into(),
+        filename: "rick.txt".to_string(),
+        size: 23,
+        comment: String::new(),
+    };
+    let file_hash = file.compute_file_hash();
+    let mut content = RevisionContent {
+        file: Some(file),
+        content: RevisionContentContent(vec![ContentSlot::FileHash(file_hash)]),
+        content_hash: Hash::default(),
+    };
+    content.content_hash = content.compute_content_hash();
+    content.verify_content_hash().expect("correct file content rejected");
+
+    content.file.as_mut().unwrap().data = b"Never gonna let you down".to_vec().into();
+    assert!(matches!(
+        content.verify_content_hash(),
+        Err(ContentHashError::FileHashMismatch(_))
+    ));
+}
+
+#[test]
+fn content_slots_keep_order_and_text() {
+    let rev = crate::tests::fixtures::receiver();
+    let content = rev.content.content;
+
+    assert!(content.main().unwrap().starts_with("{{DataAccessAgreement"));
+    let keys: Vec<&str> = content.0.iter().map(ContentSlot::key).collect();
+    assert_eq!(keys, ["main", "signature-slot", "transclusion-hashes"]);
+    let written = serde_json::to_string(&content).unwrap();
+    let reread: RevisionContentContent = serde_json::from_str(&written).unwrap();
+    assert_eq!(reread, content);
+
+    let unknown: RevisionContentContent = serde_json::from_str(
+        r#"{"zeta": "1", "file_hash": "ABC", "main": "text", "alpha": "2"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        serde_json::to_string(&unknown).unwrap(),
+        r#"{"zeta":"1","file_hash":"ABC","main":"text","alpha":"2"}"#
+    );
+    assert_eq!(unknown.file_hash(), None);
+    assert_eq!(unknown.get("alpha").map(ContentSlot::value).as_deref(), Some("2"));
+}
@@ -1,7 +1,8 @@
 //! Hash module defines the `Hash` struct, which wraps a cryptographic hash value and provides utility methods for serialization, deserialization, and type conversions.
 
 
-use crate::models::stack_str::{StackStr, from_hex};
+use crate::error::{read_hex, ReadError};
+use crate::models::stack_str::StackStr;
 
 
 // Represents a cryptographic hash, specifically a SHA-3 512-bit hash.
@@ -38,13 +39,18 @@ impl Hash {
 
 
 impl std::str::FromStr for Hash {
-    // todo: err
-    /// Error type for failing parsing, represented as ().
-    type Err = ();
+    /// Error type for failing parsing, see [`ReadError`].
+    type Err = ReadError;
 
-    /// Parses the hex string into a `Hash`.
+    /// Parses the lowercase hex string (without prefix) into a `Hash`.
+    ///
+    /// Hashes are written without a prefix, so a `0x` prefix is rejected with
+    /// [`ReadError::UnexpectedPrefix`] instead of being stripped.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Hash(from_hex(s).ok_or(())?.into()))
+        if s.starts_with("0x") {
+            return Err(ReadError::UnexpectedPrefix);
+        }
+        Ok(Hash(read_hex::<64>(s, 0)?.into()))
     }
 }
 
@@ -135,7 +141,7 @@ impl<'de> serde::Deserialize<'de> for Hash {
     {
         let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
         s.parse()
-            .map_err(|_| serde::de::Error::custom("Invalid sha3_512 hash"))
+            .map_err(|e| serde::de::Error::custom(format!("Invalid sha3_512 hash: {e}")))
     }
 }
 
@@ -161,6 +167,32 @@ impl serde::Serialize for Hash {
     }
 }
 
+/// A stored hash that differs from the hash recomputed from the data it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashMismatch {
+    /// The hash stored in the revision.
+    pub expected: Hash,
+    /// The hash recomputed from the revision data.
+    pub computed: Hash,
+}
+
+impl std::fmt::Display for HashMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {}, computed {}", self.expected, self.computed)
+    }
+}
+
+/// Lists `mismatches` as `<field> mismatch: <mismatch>`, separated by `; `.
+pub(crate) fn display_mismatches<F: std::fmt::Display>(
+    mismatches: &[(F, HashMismatch)],
+) -> String {
+    mismatches
+        .iter()
+        .map(|(field, mismatch)| format!("{field} mismatch: {mismatch}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 #[test]
 fn test_read() {
     const TEST_DATA: &str = "d9e09f8529fed3b909876f34f21c7148d73de01d82f8aee43c52d9ee2601999ddcbf4593a19baac497d9d83bb98c94c2508b8157efafcd6484cbca7c4953af5f";
@@ -174,6 +206,18 @@ fn test_read() {
         "0xd9e09f8529fed3b909876F34f21c7148d73de01d82f8aEe43c52d9ee2601999dDcbf4593a19baac497d9d83bb98c94c2508b8157efafcd6484cbca7c4953af5f";
     <Hash as std::str::FromStr>::from_str(TEST_DATA_WITH_UPPER)
         .expect_err("Accepted data witH mIxeD cAsE.");
+    assert_eq!(
+        TEST_DATA_NOPREFIX.parse::<Hash>(),
+        Err(ReadError::UnexpectedPrefix)
+    );
+    assert_eq!(
+        TEST_DATA_WITH_UPPER[2..].parse::<Hash>(),
+        Err(ReadError::NotLowercase { offset: 21 })
+    );
+    assert_eq!(
+        TEST_DATA[1..].parse::<Hash>(),
+        Err(ReadError::WrongLength { expected: 128, found: 127 })
+    );
 }
 
 #[test]
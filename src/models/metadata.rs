@@ -1,6 +1,11 @@
 //! It defines structures related to revision metadata, including `ExportRevisionMetadata` for export context and `RevisionMetadata` for revision-specific context.
 
-use crate::models::{hash::Hash, timestamp::Timestamp};
+use sha3::Digest;
+
+use crate::models::{
+    hash::{display_mismatches, Hash, HashMismatch},
+    timestamp::Timestamp,
+};
 
 // todo! remove, this is an abomination
 #[doc(hidden)]
@@ -28,6 +33,34 @@ fn opt_hash_ser<S: serde::Serializer>(
     }
 }
 
+/// Reads an optional hash that is written as `""` (or `null`) when absent.
+#[doc(hidden)]
+fn empty_hash_de<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Hash>, D::Error> {
+    use serde::Deserialize;
+    match <Option<std::borrow::Cow<'de, str>>>::deserialize(deserializer)?.as_deref() {
+        None | Some("") => Ok(None),
+        Some(s) => s
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom("Invalid sha3_512 hash")),
+    }
+}
+
+/// Writes an optional hash as `""` when absent.
+#[doc(hidden)]
+fn empty_hash_ser<S: serde::Serializer>(
+    opt_hash: &Option<Hash>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::Serialize;
+    match &opt_hash {
+        Some(hash) => hash.serialize(serializer),
+        None => serializer.serialize_str(""),
+    }
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default)]
 /// Contains context information for this revision
 pub struct RevisionMetadata {
@@ -36,11 +69,109 @@ pub struct RevisionMetadata {
     // todo! remove, this is an abomination
     #[serde(deserialize_with = "opt_hash_de", serialize_with = "opt_hash_ser")]
     pub previous_verification_hash: Option<Hash>,
+    /// Tip of the branch merged into this revision, `""` in JSON if it is no merge.
+    #[serde(
+        default,
+        deserialize_with = "empty_hash_de",
+        serialize_with = "empty_hash_ser"
+    )]
+    pub merge_hash: Option<Hash>,
     pub metadata_hash: Hash,
     pub verification_hash: Hash,
-    // pub merge_hash : Option<Hash>
 }
 
+impl RevisionMetadata {
+    /// Computes the `metadata_hash`: the SHA3-512 digest of `domain_id`, `time_stamp`,
+    /// `previous_verification_hash` (empty for a genesis revision) and `merge_hash`
+    /// (empty unless the revision is a merge), as `calculateMetadataHash` of the
+    /// aqua-verifier-js reference implementation of protocol v1.1.
+    pub fn compute_metadata_hash(&self) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        hasher.update(self.domain_id.as_bytes());
+        hasher.update(self.time_stamp.to_string().as_bytes());
+        for hash in [self.previous_verification_hash, self.merge_hash].into_iter().flatten() {
+            hasher.update(hash.to_stackstr().as_bytes());
+        }
+        Hash::from(hasher.finalize())
+    }
+
+    /// Computes the `verification_hash` from the revision's `content_hash`, the stored
+    /// `metadata_hash` and the `signature_hash` and `witness_hash` of the previous revision.
+    pub fn compute_verification_hash(
+        &self,
+        content_hash: Hash,
+        previous_signature_hash: Option<Hash>,
+        previous_witness_hash: Option<Hash>,
+    ) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        hasher.update(content_hash.to_stackstr().as_bytes());
+        hasher.update(self.metadata_hash.to_stackstr().as_bytes());
+        for hash in [previous_signature_hash, previous_witness_hash].into_iter().flatten() {
+            hasher.update(hash.to_stackstr().as_bytes());
+        }
+        Hash::from(hasher.finalize())
+    }
+
+    /// Checks `metadata_hash` and `verification_hash` against recomputed values.
+    ///
+    /// # Errors
+    /// Returns a [`MetadataHashError`] listing every stored hash that disagrees.
+    pub fn verify_hashes(
+        &self,
+        content_hash: Hash,
+        previous_signature_hash: Option<Hash>,
+        previous_witness_hash: Option<Hash>,
+    ) -> Result<(), MetadataHashError> {
+        let mut mismatches = Vec::new();
+        let computed = self.compute_metadata_hash();
+        if computed != self.metadata_hash {
+            mismatches.push((
+                MetadataHashField::MetadataHash,
+                HashMismatch { expected: self.metadata_hash, computed },
+            ));
+        }
+        let computed = self.compute_verification_hash(
+            content_hash,
+            previous_signature_hash,
+            previous_witness_hash,
+        );
+        if computed != self.verification_hash {
+            mismatches.push((
+                MetadataHashField::VerificationHash,
+                HashMismatch { expected: self.verification_hash, computed },
+            ));
+        }
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(MetadataHashError(mismatches))
+        }
+    }
+}
+
+/// The hashes stored in [`RevisionMetadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataHashField {
+    /// `metadata_hash`, over the metadata fields.
+    MetadataHash,
+    /// `verification_hash`, over the content, the metadata and the previous revision.
+    VerificationHash,
+}
+
+impl std::fmt::Display for MetadataHashField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MetadataHashField::MetadataHash => "metadata_hash",
+            MetadataHashField::VerificationHash => "verification_hash",
+        })
+    }
+}
+
+/// The stored hashes of a [`RevisionMetadata`] that disagree with the recomputed ones.
+#[derive(thiserror::Error, Debug)]
+#[error("{}", display_mismatches(.0))]
+pub struct MetadataHashError(pub Vec<(MetadataHashField, HashMismatch)>);
+
 // this is hopefully temporary. revisions do not have verification_hash on export.
 /// Contains context information of the revision on export.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default)]
@@ -50,7 +181,68 @@ pub struct ExportRevisionMetadata {
     // todo! remove, this is an abomination
     #[serde(deserialize_with = "opt_hash_de", serialize_with = "opt_hash_ser")]
     pub previous_verification_hash: Option<Hash>,
+    #[serde(
+        default,
+        deserialize_with = "empty_hash_de",
+        serialize_with = "empty_hash_ser"
+    )]
+    pub merge_hash: Option<Hash>,
     pub metadata_hash: Hash,
     pub signature: Option<super::signature::RevisionSignature>,
     pub witness: Option<super::witness::RevisionWitness>,
 }
+
+#[test]
+fn hashes_of_signed_revision() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let previous_signature_hash = previous.signature.map(|s| s.signature_hash);
+
+    previous
+        .metadata
+        .verify_hashes(previous.content.content_hash, None, None)
+        .expect("genesis hashes rejected");
+    rev.metadata
+        .verify_hashes(rev.content.content_hash, previous_signature_hash, None)
+        .expect("hashes rejected");
+
+    let mut tampered = rev.metadata.clone();
+    tampered.domain_id.push('0');
+    let err = tampered
+        .verify_hashes(rev.content.content_hash, previous_signature_hash, None)
+        .expect_err("tampered metadata accepted");
+    let fields: Vec<_> = err.0.iter().map(|(field, _)| *field).collect();
+    assert_eq!(fields, [MetadataHashField::MetadataHash]);
+
+    let err = rev
+        .metadata
+        .verify_hashes(rev.content.content_hash, None, None)
+        .expect_err("missing previous signature accepted");
+    let fields: Vec<_> = err.0.iter().map(|(field, _)| *field).collect();
+    assert_eq!(fields, [MetadataHashField::VerificationHash]);
+}
+
+#[test]
+fn read_write_merge_hash() {
+    let mut metadata: RevisionMetadata = serde_json::from_value(serde_json::json!({
+        "domain_id": "7c463f5324",
+        "time_stamp": "20240704094537",
+        "previous_verification_hash": "",
+        "merge_hash": "",
+        "metadata_hash": Hash::default(),
+        "verification_hash": Hash::default(),
+    }))
+    .expect("empty merge_hash rejected");
+    assert_eq!(metadata.merge_hash, None);
+    assert_eq!(serde_json::to_value(&metadata).unwrap()["merge_hash"], "");
+
+    let without_merge = metadata.compute_metadata_hash();
+    metadata.merge_hash = Some(Hash::from([1; 64]));
+    assert_ne!(metadata.compute_metadata_hash(), without_merge);
+    let written = serde_json::to_value(&metadata).unwrap();
+    let reread: RevisionMetadata = serde_json::from_value(written.clone()).unwrap();
+    assert_eq!(reread.merge_hash, metadata.merge_hash);
+
+    let mut invalid = written;
+    invalid["merge_hash"] = "not a hash".into();
+    serde_json::from_value::<RevisionMetadata>(invalid).expect_err("invalid merge_hash accepted");
+}
@@ -50,3 +50,136 @@ pub struct HashChain {
     #[serde(with = "tuple_vec_map")]
     pub revisions: Vec<(Hash, Revision)>,
 }
+
+impl HashChain {
+    /// Checks that the revisions form a single linear chain.
+    ///
+    /// Every key must equal the `verification_hash` of its revision, exactly one
+    /// genesis revision must exist and match `genesis_hash`, `chain_height` must equal
+    /// the number of revisions and, walking from genesis, every `previous_verification_hash`
+    /// must link to the prior revision.
+    ///
+    /// # Errors
+    /// Returns the first inconsistency found as a [`ChainError`].
+    pub fn verify(&self) -> Result<(), ChainError> {
+        let mut genesis = None;
+        let mut children = std::collections::HashMap::new();
+        for (index, (key, rev)) in self.revisions.iter().enumerate() {
+            if *key != rev.metadata.verification_hash {
+                return Err(ChainError::KeyMismatch { index, key: *key });
+            }
+            match rev.metadata.previous_verification_hash {
+                None => match genesis {
+                    None => genesis = Some(index),
+                    Some(first) => return Err(ChainError::MultipleGenesis { first, second: index }),
+                },
+                Some(previous) => {
+                    if children.insert(previous, index).is_some() {
+                        return Err(ChainError::Fork { index, previous });
+                    }
+                }
+            }
+        }
+        let genesis = genesis.ok_or(ChainError::NoGenesis)?;
+        let genesis_hash = self.revisions[genesis].0;
+        if self.genesis_hash != genesis_hash.to_string() {
+            return Err(ChainError::GenesisHashMismatch {
+                expected: self.genesis_hash.clone(),
+                found: genesis_hash,
+            });
+        }
+        if self.chain_height != self.revisions.len() as u64 {
+            return Err(ChainError::ChainHeightMismatch {
+                expected: self.chain_height,
+                found: self.revisions.len() as u64,
+            });
+        }
+
+        let mut visited = vec![false; self.revisions.len()];
+        let mut current = genesis_hash;
+        visited[genesis] = true;
+        while let Some(&index) = children.get(&current) {
+            visited[index] = true;
+            current = self.revisions[index].0;
+        }
+        match visited.iter().position(|v| !v) {
+            Some(index) => Err(ChainError::BrokenLink {
+                index,
+                previous: self.revisions[index]
+                    .1
+                    .metadata
+                    .previous_verification_hash
+                    .unwrap_or_default(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Error types for [`HashChain::verify`].
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ChainError {
+    /// A key in `revisions` differs from its revision's `verification_hash`.
+    #[error("revision {index}: key {key} is not the verification_hash of the revision")]
+    KeyMismatch { index: usize, key: Hash },
+
+    /// No revision without `previous_verification_hash` exists.
+    #[error("chain has no genesis revision")]
+    NoGenesis,
+
+    /// More than one revision has no `previous_verification_hash`.
+    #[error("revisions {first} and {second} are both genesis revisions")]
+    MultipleGenesis { first: usize, second: usize },
+
+    /// `genesis_hash` differs from the verification hash of the genesis revision.
+    #[error("genesis_hash {expected} does not match genesis revision {found}")]
+    GenesisHashMismatch { expected: String, found: Hash },
+
+    /// `chain_height` differs from the number of revisions.
+    #[error("chain_height is {expected}, but chain has {found} revisions")]
+    ChainHeightMismatch { expected: u64, found: u64 },
+
+    /// Two revisions share the same previous revision.
+    #[error("revision {index}: previous revision {previous} already has a successor")]
+    Fork { index: usize, previous: Hash },
+
+    /// A revision cannot be reached from genesis.
+    #[error("revision {index}: previous revision {previous} is not in the chain")]
+    BrokenLink { index: usize, previous: Hash },
+}
+
+#[test]
+fn verify_hash_chain() {
+    let (genesis, second) = crate::tests::fixtures::signed_pair();
+    let mut chain = HashChain {
+        genesis_hash: genesis.metadata.verification_hash.to_string(),
+        domain_id: genesis.metadata.domain_id.clone(),
+        title: "Data Access Agreement".to_string(),
+        namespace: 0,
+        chain_height: 2,
+        revisions: vec![
+            (second.metadata.verification_hash, second.clone()),
+            (genesis.metadata.verification_hash, genesis.clone()),
+        ],
+    };
+    chain.verify().expect("valid chain rejected");
+
+    chain.chain_height = 3;
+    assert!(matches!(chain.verify(), Err(ChainError::ChainHeightMismatch { .. })));
+    chain.chain_height = 2;
+
+    chain.revisions[0].1.metadata.previous_verification_hash = Some(Hash::default());
+    chain.revisions[0].0 = Hash::default();
+    assert!(matches!(chain.verify(), Err(ChainError::KeyMismatch { index: 0, .. })));
+    chain.revisions[0].0 = second.metadata.verification_hash;
+    assert_eq!(
+        chain.verify(),
+        Err(ChainError::BrokenLink { index: 0, previous: Hash::default() })
+    );
+
+    chain.revisions[0].1.metadata.previous_verification_hash = None;
+    assert_eq!(
+        chain.verify(),
+        Err(ChainError::MultipleGenesis { first: 0, second: 1 })
+    );
+}
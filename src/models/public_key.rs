@@ -6,7 +6,8 @@ use std::ops::Deref;
 
 use crate::{
     crypt,
-    models::stack_str::{from_hex, StackStr},
+    error::{read_prefixed_hex, ReadError},
+    models::stack_str::StackStr,
 };
 
 /// A wrapper for `libsecp256k1::PublickKey` with additional methods
@@ -92,7 +93,7 @@ impl Deref for PublicKey {
 /// This allows a `PublicKey` to be parsed from a string.
 impl std::str::FromStr for PublicKey {
     /// The error type returned when parsing fails.
-    type Err = ();
+    type Err = ReadError;
 
     /// Parses a `PublicKey` from a hexadecimal string.
     ///
@@ -101,19 +102,16 @@ impl std::str::FromStr for PublicKey {
     ///
     /// # Returns
     /// - `Ok(PublicKey)` if the string is successfully parsed.
-    /// - `Err(())` if the string is invalid.
+    /// - `Err(ReadError)` if the string is invalid.
     ///
     /// # Errors
-    /// - Returns an error if the string contains uppercase characters.
-    /// - Returns an error if the string does not start with "0x".
-    /// - Returns an error if the string cannot be parsed as a valid `PublicKey`.
+    /// - [`ReadError::NoPrefix`] if the string does not start with "0x".
+    /// - [`ReadError::NotHex`], [`ReadError::NotLowercase`] or [`ReadError::WrongLength`]
+    ///   if the rest is not 130 lowercase hex digits.
+    /// - [`ReadError::InvalidCurvePoint`] if the bytes are not a valid `PublicKey`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.to_ascii_lowercase() != s {
-            return Err(());
-        }
-        let s = s.strip_prefix("0x").ok_or(())?;
-        let h = from_hex(s).ok_or(())?;
-        h.try_into().map_err(|_| ())
+        let h: [u8; 65] = read_prefixed_hex(s)?;
+        h.try_into().map_err(ReadError::InvalidCurvePoint)
     }
 }
 
@@ -155,7 +153,7 @@ impl<'de> serde::Deserialize<'de> for PublicKey {
     {
         let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
         s.parse()
-            .map_err(|_| serde::de::Error::custom("not a valid signature (or maybe not supported)"))
+            .map_err(|e| serde::de::Error::custom(format!("Invalid public key: {e}")))
     }
 }
 
@@ -197,6 +195,23 @@ fn test_read() {
     const TEST_DATA_WITH_UPPER: &str = "0x04062274ed5bba92b9Ab6b8687a86d87066d3dbac83e4f7e0e996a4d163e1bB294a75d8bBef8c9b2425bf7c020c7Fe298580bc37fe8562227cb50e574dabb79701";
     <PublicKey as std::str::FromStr>::from_str(TEST_DATA_WITH_UPPER)
         .expect_err("accepted public key with Uppercase Letters.");
+    assert!(matches!(
+        TEST_DATA_TOO_LONG.parse::<PublicKey>(),
+        Err(ReadError::WrongLength { expected: 130, found: 133 })
+    ));
+    assert!(matches!(
+        TEST_DATA_NOPREFIX.parse::<PublicKey>(),
+        Err(ReadError::NoPrefix)
+    ));
+    assert!(matches!(
+        TEST_DATA_WITH_UPPER.parse::<PublicKey>(),
+        Err(ReadError::NotLowercase { offset: 20 })
+    ));
+    let not_on_curve = format!("0x04{}", "00".repeat(64));
+    assert!(matches!(
+        not_on_curve.parse::<PublicKey>(),
+        Err(ReadError::InvalidCurvePoint(_))
+    ));
 }
 
 #[test]
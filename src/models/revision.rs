@@ -22,6 +22,9 @@ use crate::models::witness::RevisionWitness;
 /// signature and witness to define comprehensive document revisions.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default)]
 pub struct Revision {
+    /// Which hashes of the previous revision are part of the `verification_hash`.
+    #[serde(default)]
+    pub verification_context: VerificationContext,
     /// The content of the revision.
     pub content: content::RevisionContent,
     /// Metadata associated with the revision.
@@ -32,6 +35,108 @@ pub struct Revision {
     pub witness: Option<witness::RevisionWitness>,
 }
 
+/// Records which hashes of the previous revision are part of the `verification_hash`
+/// of a revision.
+///
+/// The flags of genesis revisions carry no meaning, as there is no previous revision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerificationContext {
+    /// The previous revision is signed, its `signature_hash` is included.
+    pub has_previous_signature: bool,
+    /// The previous revision is witnessed, its `witness_hash` is included.
+    pub has_previous_witness: bool,
+}
+
+impl VerificationContext {
+    /// Derives the context of a revision following `previous`.
+    pub fn from_previous(previous: Option<&Revision>) -> Self {
+        VerificationContext {
+            has_previous_signature: previous.is_some_and(|p| p.signature.is_some()),
+            has_previous_witness: previous.is_some_and(|p| p.witness.is_some()),
+        }
+    }
+}
+
+impl Revision {
+    /// Computes the `verification_hash` of the revision, including the signature and
+    /// witness hashes of `previous` as its `verification_context` demands.
+    ///
+    /// # Errors
+    /// Returns an error if the context demands a hash `previous` does not have.
+    pub fn compute_verification_hash(
+        &self,
+        previous: Option<&Revision>,
+    ) -> Result<Hash, VerificationContextError> {
+        let (signature_hash, witness_hash) = match previous {
+            None => (None, None),
+            Some(prev) => {
+                let context = self.verification_context;
+                let signature_hash = match &prev.signature {
+                    Some(sig) if context.has_previous_signature => Some(sig.signature_hash),
+                    None if context.has_previous_signature => {
+                        return Err(VerificationContextError::MissingPreviousSignature)
+                    }
+                    _ => None,
+                };
+                let witness_hash = match &prev.witness {
+                    Some(wit) if context.has_previous_witness => Some(wit.witness_hash),
+                    None if context.has_previous_witness => {
+                        return Err(VerificationContextError::MissingPreviousWitness)
+                    }
+                    _ => None,
+                };
+                (signature_hash, witness_hash)
+            }
+        };
+        Ok(self.metadata.compute_verification_hash(
+            self.content.content_hash,
+            signature_hash,
+            witness_hash,
+        ))
+    }
+
+    /// Checks that `verification_context` matches the one derived from `previous`.
+    /// Genesis revisions are not checked.
+    ///
+    /// # Errors
+    /// Returns [`VerificationContextError::ContextMismatch`] if the contexts differ.
+    pub fn verify_verification_context(
+        &self,
+        previous: Option<&Revision>,
+    ) -> Result<(), VerificationContextError> {
+        if previous.is_none() {
+            return Ok(());
+        }
+        let derived = VerificationContext::from_previous(previous);
+        if derived != self.verification_context {
+            return Err(VerificationContextError::ContextMismatch {
+                stored: self.verification_context,
+                derived,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error types for the `verification_context` of a revision.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum VerificationContextError {
+    /// The context includes the previous signature, but the previous revision is unsigned.
+    #[error("context includes previous signature, but previous revision is not signed")]
+    MissingPreviousSignature,
+
+    /// The context includes the previous witness, but the previous revision is not witnessed.
+    #[error("context includes previous witness, but previous revision is not witnessed")]
+    MissingPreviousWitness,
+
+    /// The stored context differs from the one derived from the previous revision.
+    #[error("verification_context {stored:?} does not match previous revision ({derived:?})")]
+    ContextMismatch {
+        stored: VerificationContext,
+        derived: VerificationContext,
+    },
+}
+
 /// A reference to a specific revision.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct RevisionReference {
@@ -137,3 +242,31 @@ fn main_page_signed() {
     dbg!(&x);
     assert!(x.signature.is_some());
 }
+
+#[test]
+fn verification_context() {
+    let (previous, mut rev) = crate::tests::fixtures::signed_pair();
+    assert!(rev.verification_context.has_previous_signature);
+    assert_eq!(rev.verification_context, VerificationContext::from_previous(Some(&previous)));
+    rev.verify_verification_context(Some(&previous)).expect("context rejected");
+    assert_eq!(
+        rev.compute_verification_hash(Some(&previous)),
+        Ok(rev.metadata.verification_hash)
+    );
+
+    rev.verification_context.has_previous_signature = false;
+    assert_ne!(
+        rev.compute_verification_hash(Some(&previous)),
+        Ok(rev.metadata.verification_hash)
+    );
+    assert!(matches!(
+        rev.verify_verification_context(Some(&previous)),
+        Err(VerificationContextError::ContextMismatch { .. })
+    ));
+
+    rev.verification_context.has_previous_witness = true;
+    assert_eq!(
+        rev.compute_verification_hash(Some(&previous)),
+        Err(VerificationContextError::MissingPreviousWitness)
+    );
+}
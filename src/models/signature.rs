@@ -2,9 +2,12 @@
 
 
 use ethaddr::Address;
+use sha3::Digest;
 
-use crate::models::stack_str::{StackStr, from_hex};
-use crate::models::hash::Hash;
+use crate::crypt;
+use crate::error::read_prefixed_hex;
+use crate::models::stack_str::StackStr;
+use crate::models::hash::{Hash, HashMismatch};
 
 use super::public_key::PublicKey;
 
@@ -135,28 +138,8 @@ impl TryFrom<[u8; 65]> for Signature {
 // }
 
 
-/// Error types for parsing or handling `Signature`.
-///
-/// This enumeration represents the different kinds of errors that can occur
-/// during parsing or handling of a `Signature`.
-#[derive(thiserror::Error, Debug)]
-pub enum ReadError {
-    /// Error when the input string is not in ASCII lowercase.
-    #[error("ascii or smth idk")]
-    NotAsciiLower,
-
-    /// Error when the string is missing the required `0x` prefix.
-    #[error("goddamit WITH prefix")]
-    NoPrefix,
-
-    /// Error when the input contains non-hexadecimal characters.
-    #[error("stay with HEX inputs only")]
-    NotHex,
-
-    /// Error when `libsecp256k1` fails to parse or handle the input.
-    #[error("libsecp256k1: {0}")]
-    DecryptFail(#[from] libsecp256k1::Error),
-}
+/// Error type for parsing a `Signature`, see [`crate::error::ReadError`].
+pub use crate::error::ReadError;
 
 /// Implements the `FromStr` trait to parse a `Signature` from a string.
 impl std::str::FromStr for Signature {
@@ -174,12 +157,8 @@ impl std::str::FromStr for Signature {
     /// - `Ok(Signature)` if the parsing succeeds.
     /// - `Err(ReadError)` if the string is invalid or parsing fails.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.to_ascii_lowercase() != s {
-            return Err(ReadError::NotAsciiLower);
-        }
-        let s = s.strip_prefix("0x").ok_or(ReadError::NoPrefix)?;
-        let h = from_hex(s).ok_or(ReadError::NotHex)?;
-        h.try_into().map_err(ReadError::DecryptFail)
+        let h: [u8; 65] = read_prefixed_hex(s)?;
+        h.try_into().map_err(ReadError::InvalidSignature)
     }
 }
 
@@ -199,7 +178,7 @@ impl<'de> serde::Deserialize<'de> for Signature {
     {
         let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
         s.parse()
-            .map_err(|problem| serde::de::Error::custom(format!("Signature problem is: {}", problem)))
+            .map_err(|e| serde::de::Error::custom(format!("Invalid signature: {e}")))
     }
 }
 
@@ -244,6 +223,104 @@ pub struct RevisionSignature {
     pub wallet_address: Address,
 }
 
+impl RevisionSignature {
+    /// Computes the `signature_hash`: the SHA3-512 digest of the `0x`-prefixed
+    /// signature and public key.
+    pub fn compute_signature_hash(&self) -> Hash {
+        let mut hasher = crypt::Hasher::default();
+        hasher.update(self.signature.to_stackstr().as_bytes());
+        hasher.update(self.public_key.to_stackstr().as_bytes());
+        Hash::from(hasher.finalize())
+    }
+
+    /// Recovers the public key that signed `verification_hash` with `personal_sign`.
+    ///
+    /// # Errors
+    /// Returns an error if no public key can be recovered from the signature.
+    pub fn recover_public_key(
+        &self,
+        verification_hash: &Hash,
+    ) -> Result<PublicKey, libsecp256k1::Error> {
+        let message = libsecp256k1::Message::parse(&personal_message_hash(verification_hash));
+        libsecp256k1::recover(&message, &self.signature.signature, &self.signature.recovery_id)
+            .map(PublicKey::from)
+    }
+
+    /// Checks that the signature over `verification_hash` was made by `public_key`
+    /// and that `wallet_address` belongs to it.
+    ///
+    /// # Errors
+    /// See [`SignatureError`].
+    pub fn verify_signer(&self, verification_hash: &Hash) -> Result<(), SignatureError> {
+        let recovered = self.recover_public_key(verification_hash)?;
+        if recovered != self.public_key {
+            return Err(SignatureError::PublicKeyMismatch { recovered });
+        }
+        let derived = Address::from(recovered);
+        if derived != self.wallet_address {
+            return Err(SignatureError::WalletAddressMismatch {
+                expected: self.wallet_address,
+                derived,
+            });
+        }
+        Ok(())
+    }
+
+    /// Fully verifies the signature of the revision with the given `verification_hash`:
+    /// the signer (see [`RevisionSignature::verify_signer`]) and the `signature_hash`.
+    ///
+    /// # Errors
+    /// See [`SignatureError`].
+    pub fn verify(&self, verification_hash: &Hash) -> Result<(), SignatureError> {
+        self.verify_signer(verification_hash)?;
+        let computed = self.compute_signature_hash();
+        if computed != self.signature_hash {
+            return Err(SignatureError::SignatureHashMismatch(Box::new(HashMismatch {
+                expected: self.signature_hash,
+                computed,
+            })));
+        }
+        Ok(())
+    }
+}
+
+/// The message a wallet signs for a revision with the given `verification_hash`.
+pub fn signing_message(verification_hash: &Hash) -> String {
+    format!(
+        "I sign the following page verification_hash: [0x{}]",
+        verification_hash.to_stackstr()
+    )
+}
+
+/// The EIP-191 (`personal_sign`) Keccak-256 digest of [`signing_message`].
+pub fn personal_message_hash(verification_hash: &Hash) -> [u8; 32] {
+    let message = signing_message(verification_hash);
+    let mut hasher = crypt::Keccak256::default();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+    hasher.update(message.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Error types for verifying a [`RevisionSignature`].
+#[derive(thiserror::Error, Debug)]
+pub enum SignatureError {
+    /// No public key can be recovered from the signature.
+    #[error("cannot recover public key: {0}")]
+    Recovery(#[from] libsecp256k1::Error),
+
+    /// The signature was made by a different key than `public_key`.
+    #[error("signature was made by {recovered}, not the stated public key")]
+    PublicKeyMismatch { recovered: PublicKey },
+
+    /// `wallet_address` does not belong to the signing key.
+    #[error("wallet address {expected} does not match signer {derived}")]
+    WalletAddressMismatch { expected: Address, derived: Address },
+
+    /// `signature_hash` does not match the signature and public key.
+    #[error("signature hash mismatch: {0}")]
+    SignatureHashMismatch(Box<HashMismatch>),
+}
+
 #[test]
 fn test_read() {
     const TEST_DATA: &str = 
@@ -263,6 +340,15 @@ fn test_read() {
         "0xf0d0cadd0c82aDe49db1e3443615dca67856E94b85D5590a2970d442e09b96E66fe9326f55A1e24b95f960f985bb524200be428d7084833db9ce7e778e2932121C";
     <Signature as std::str::FromStr>::from_str(TEST_DATA_WITH_UPPER)
         .expect_err("Accepted signature with miXeD caSe.");
+    assert!(matches!(
+        TEST_DATA_WITH_UPPER.parse::<Signature>(),
+        Err(ReadError::NotLowercase { offset: 15 })
+    ));
+    let bad_recovery_id = format!("{}00", &TEST_DATA[..TEST_DATA.len() - 2]);
+    assert!(matches!(
+        bad_recovery_id.parse::<Signature>(),
+        Err(ReadError::InvalidSignature(_))
+    ));
 }
 
 #[test]
@@ -271,3 +357,29 @@ fn test_write() {
     let signature_thing: Signature = TEST_DATA.parse().expect("Correct Signature not read.");
     assert_eq!(TEST_DATA, &*signature_thing.to_stackstr(), "stuff broke");
 }
+
+#[test]
+fn verify_metamask_signatures() {
+    for data in [
+        include_str!("../tests/test_data/DAA_SIG_SENDER_NO_WIT.json"),
+        include_str!("../tests/test_data/DAA_SIG_SENDER_NO_WIT_NO_TERM.json"),
+        include_str!("../tests/test_data/DAA_SIG_RECEIVER_NO_WIT.json"),
+        include_str!("../tests/test_data/DAA_SIG_RECEIVER_2_NO_WIT.json"),
+    ] {
+        let rev: super::revision::Revision = serde_json::from_str(data).expect("failed to parse");
+        let sig = rev.signature.expect("fixture is signed");
+        sig.verify(&rev.metadata.verification_hash).expect("MetaMask signature rejected");
+
+        let mut wrong_wallet = sig.clone();
+        wrong_wallet.wallet_address = Address([0; 20]);
+        assert!(matches!(
+            wrong_wallet.verify(&rev.metadata.verification_hash),
+            Err(SignatureError::WalletAddressMismatch { .. })
+        ));
+
+        assert!(matches!(
+            sig.verify(&Hash::default()),
+            Err(SignatureError::PublicKeyMismatch { .. } | SignatureError::Recovery(_))
+        ));
+    }
+}
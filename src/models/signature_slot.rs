@@ -0,0 +1,173 @@
+//! Typed entries of the `signature-slot` content slot.
+//!
+//! When a revision is signed, the next revision of the page appends an entry with the
+//! signer's address to the slot, stamped with its own `time_stamp`. The slot holds these
+//! entries as a JSON list (inside a string).
+
+use ethaddr::Address;
+
+use crate::models::revision::Revision;
+use crate::models::timestamp::Timestamp;
+
+/// A recorded signature of a revision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SignatureSlot {
+    /// Wallet address of the signer.
+    pub user: Address,
+    /// `time_stamp` of the revision that recorded the signature.
+    pub timestamp: Timestamp,
+}
+
+/// Parses the value of a `signature-slot` slot.
+pub fn parse_signature_slots(raw: &str) -> Result<Vec<SignatureSlot>, serde_json::Error> {
+    serde_json::from_str(raw)
+}
+
+/// Writes entries as the value of a `signature-slot` slot, indented like PKC writes it.
+pub fn write_signature_slots(slots: &[SignatureSlot]) -> String {
+    use serde::Serialize;
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    // Serializing addresses and timestamps into memory cannot fail.
+    slots
+        .serialize(&mut serializer)
+        .expect("signature slots are always serializable");
+    String::from_utf8(out).expect("serde_json writes utf-8")
+}
+
+/// Checks the `signature-slot` of `rev` against the revision it follows.
+///
+/// Entries are only ever appended: all entries of `previous` have to be kept, and a new
+/// entry has to record the signature of `previous` at the `time_stamp` of `rev`.
+pub fn verify_signature_slots(
+    rev: &Revision,
+    previous: Option<&Revision>,
+) -> Result<(), SignatureSlotError> {
+    let entries = rev.content.content.signature_slots()?;
+    let recorded = match previous {
+        Some(previous) => previous.content.content.signature_slots()?,
+        None => Vec::new(),
+    };
+    if !entries.starts_with(&recorded) {
+        return Err(SignatureSlotError::EntriesRewritten);
+    }
+    match &entries[recorded.len()..] {
+        [] => Ok(()),
+        [entry] => {
+            let signature = previous
+                .ok_or(SignatureSlotError::MissingPrevious)?
+                .signature
+                .as_ref()
+                .ok_or(SignatureSlotError::UnsignedPrevious)?;
+            if entry.user != signature.wallet_address {
+                return Err(SignatureSlotError::WalletMismatch {
+                    entry: entry.user,
+                    signer: signature.wallet_address,
+                });
+            }
+            if entry.timestamp != rev.metadata.time_stamp {
+                return Err(SignatureSlotError::TimestampMismatch {
+                    entry: entry.timestamp,
+                    time_stamp: rev.metadata.time_stamp,
+                });
+            }
+            Ok(())
+        }
+        added => Err(SignatureSlotError::TooManyEntries(added.len())),
+    }
+}
+
+/// Error types for checking the `signature-slot` of a revision.
+#[derive(thiserror::Error, Debug)]
+pub enum SignatureSlotError {
+    /// The slot is not a JSON list of entries.
+    #[error("invalid signature-slot: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    /// Entries of the previous revision were removed or changed.
+    #[error("signature-slot does not keep the entries of the previous revision")]
+    EntriesRewritten,
+
+    /// A revision can only record the signature of the revision it follows.
+    #[error("signature-slot adds {0} entries, at most one is allowed")]
+    TooManyEntries(usize),
+
+    /// An entry was added, but there is no previous revision that could have been signed.
+    #[error("signature-slot entry added without a previous revision")]
+    MissingPrevious,
+
+    /// An entry was added, but the previous revision is not signed.
+    #[error("signature-slot entry added, but the previous revision is not signed")]
+    UnsignedPrevious,
+
+    /// The added entry names a different signer.
+    #[error("signature-slot entry of {entry} does not match signer {signer}")]
+    WalletMismatch { entry: Address, signer: Address },
+
+    /// The added entry is not stamped with the `time_stamp` of the revision.
+    #[error("signature-slot entry at {entry} does not match time_stamp {time_stamp}")]
+    TimestampMismatch {
+        entry: Timestamp,
+        time_stamp: Timestamp,
+    },
+}
+
+#[test]
+fn read_write_signature_slots() {
+    let rev = crate::tests::fixtures::receiver();
+    let raw = rev
+        .content
+        .content
+        .signature_slot()
+        .expect("fixture has a signature-slot");
+
+    let slots = parse_signature_slots(raw).expect("failed to parse signature-slot");
+    assert_eq!(slots.len(), 1);
+    assert_eq!(slots[0].timestamp.to_string(), "20240704094602");
+    assert_eq!(write_signature_slots(&slots), raw);
+
+    parse_signature_slots(r#"[{"user": "0x1234", "timestamp": "20240704094602"}]"#)
+        .expect_err("invalid address accepted");
+}
+
+#[test]
+fn verify_signature_slot_of_receiver() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+
+    verify_signature_slots(&previous, None).expect("revision without slot rejected");
+    verify_signature_slots(&rev, Some(&previous)).expect("signature-slot of fixture rejected");
+    assert!(matches!(
+        verify_signature_slots(&rev, None),
+        Err(SignatureSlotError::MissingPrevious)
+    ));
+
+    let mut unsigned = previous.clone();
+    unsigned.signature = None;
+    assert!(matches!(
+        verify_signature_slots(&rev, Some(&unsigned)),
+        Err(SignatureSlotError::UnsignedPrevious)
+    ));
+
+    // The receiver signed `rev`, not `previous`.
+    let mut other_signer = previous.clone();
+    other_signer.signature = rev.signature.clone();
+    assert!(matches!(
+        verify_signature_slots(&rev, Some(&other_signer)),
+        Err(SignatureSlotError::WalletMismatch { .. })
+    ));
+
+    let mut late = rev.clone();
+    late.metadata.time_stamp = previous.metadata.time_stamp;
+    assert!(matches!(
+        verify_signature_slots(&late, Some(&previous)),
+        Err(SignatureSlotError::TimestampMismatch { .. })
+    ));
+
+    // Entries are kept by revisions that record no new signature, but never dropped.
+    verify_signature_slots(&rev, Some(&rev)).expect("kept entries rejected");
+    assert!(matches!(
+        verify_signature_slots(&previous, Some(&rev)),
+        Err(SignatureSlotError::EntriesRewritten)
+    ));
+}
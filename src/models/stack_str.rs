@@ -19,11 +19,13 @@
 ///
 /// # Example
 /// ```rust
+/// use aqua_verifier_rs_types::models::stack_str::from_hex;
+///
 /// let result = from_hex::<4>("deadbeef");
 /// assert_eq!(result, Some([0xde, 0xad, 0xbe, 0xef]));
 /// ``` 
 pub fn from_hex<const SIZE: usize>(s: &str) -> Option<[u8; SIZE]> {
-    if !s.as_bytes().len() == SIZE * 2 || !s.is_ascii() {
+    if s.len() != SIZE * 2 || !s.is_ascii() {
         return None;
     }
     let mut data = [0u8; SIZE];
@@ -47,8 +49,11 @@ pub fn from_hex<const SIZE: usize>(s: &str) -> Option<[u8; SIZE]> {
 ///
 /// # Examples
 /// ```rust
-/// let stack_str = StackStr::<16>::new(*b"Hello, world!");
-/// assert_eq!(stack_str.as_ref(), "Hello, world!");
+/// use aqua_verifier_rs_types::models::stack_str::StackStr;
+///
+/// let stack_str = StackStr::<13>::new(*b"Hello, world!");
+/// let stack_str: &str = stack_str.as_ref();
+/// assert_eq!(stack_str, "Hello, world!");
 /// ```
 pub struct StackStr<const X: usize>([u8; X]);
 
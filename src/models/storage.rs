@@ -1,7 +1,8 @@
 //! Defines the `Storage` trait, which specifies an interface for a storage system.
 
 
-use std::{fmt::Debug, future::Future};
+use std::{fmt::Debug, future::Future, pin::Pin};
+use futures_core::Stream;
 use crate::models::hash::Hash;
 use crate::models::revision::Revision;
 
@@ -32,6 +33,8 @@ pub trait Storage: Sized {
 
 
     /// Stores a revision and its associated context in the storage.
+    ///
+    /// Fails if the revision is already stored, or if the revision it follows is not.
     /// 
     /// # Parameters
     /// - `rev`: The revision to store.
@@ -75,15 +78,89 @@ pub trait Storage: Sized {
     /// An asynchronous result containing a vector of hashes or an error.
     fn list(&self) -> impl Future<Output = Result<Vec<Hash>, Self::Error>> + Send;
 
+    /// Subscribes to the events of this storage.
+    ///
+    /// The stream yields every event published after this call, in order, and ends once
+    /// the storage is dropped. Events are buffered per subscriber, and storage operations
+    /// never wait for a subscriber: one whose buffer is full when an event is published
+    /// is disconnected, and its stream ends with [`StorageEvent::Lagged`] after the
+    /// buffered events. Dropping the stream unsubscribes.
+    fn subscribe(&self) -> impl Stream<Item = StorageEvent> + Send + Unpin + 'static;
+
     /// Registers an update handler to be invoked on storage updates.
+    ///
+    /// Adapter over [`Storage::subscribe`]: `f` is called with the hash of every revision
+    /// stored after this call and `"stored"`, and with the hash of every revision whose
+    /// context changed and `"context updated"`. Branch events are not passed on.
     /// 
     /// # Parameters
     /// - `f`: A callback function that takes a `Hash` and a `String` description of the update.
     /// 
     /// # Returns
     /// An asynchronous result containing an `Infallible` or an error.
+    ///
+    /// # Errors
+    /// [`UpdateHandlerError::Lagged`] once the future fell too far behind the storage and
+    /// missed events, [`UpdateHandlerError::Closed`] once the storage was dropped.
+    #[deprecated(note = "use `Storage::subscribe`")]
     fn update_handler<F: Fn(Hash, String) + Send + Sync>(
         &self,
         f: F,
-    ) -> impl Future<Output = Result<std::convert::Infallible, Self::Error>> + Send;
+    ) -> impl Future<Output = Result<std::convert::Infallible, Self::Error>> + Send
+    where
+        Self::Error: From<UpdateHandlerError>,
+    {
+        let mut events = self.subscribe();
+        async move {
+            while let Some(event) =
+                std::future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await
+            {
+                match event {
+                    StorageEvent::RevisionStored(hash) => f(hash, "stored".to_string()),
+                    StorageEvent::ContextUpdated(hash) => f(hash, "context updated".to_string()),
+                    StorageEvent::Lagged => return Err(UpdateHandlerError::Lagged.into()),
+                    _ => {}
+                }
+            }
+            Err(UpdateHandlerError::Closed.into())
+        }
+    }
+}
+
+/// A change to a storage, see [`Storage::subscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StorageEvent {
+    /// A revision was stored.
+    RevisionStored(Hash),
+
+    /// A revision was stored following `previous`, which no other stored revision follows.
+    /// Published after the [`StorageEvent::RevisionStored`] of `hash`.
+    BranchExtended { previous: Hash, hash: Hash },
+
+    /// A revision was stored following `previous`, which another stored revision already
+    /// follows. Published after the [`StorageEvent::RevisionStored`] of `hash`.
+    BranchForked { previous: Hash, hash: Hash },
+
+    /// The context stored with a revision changed.
+    ///
+    /// Reserved for storages whose contexts can change; the [`Storage`] trait has no
+    /// operation updating a context, so the backends of this crate never publish it.
+    ContextUpdated(Hash),
+
+    /// The subscriber fell behind by more events than are buffered and was disconnected.
+    /// Always the last event of its stream; the events published after it are lost.
+    Lagged,
+}
+
+/// Why the future returned by [`Storage::update_handler`] stopped.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateHandlerError {
+    /// The handler fell too far behind the storage and missed events.
+    #[error("update handler fell behind and missed events")]
+    Lagged,
+
+    /// The storage was dropped.
+    #[error("storage was dropped")]
+    Closed,
 }
@@ -22,7 +22,10 @@
 //     pub witness: Option<witness::RevisionWitness>,
 // }
 
+#[cfg(test)]
 use crate::models::revision::Revision;
+#[cfg(test)]
+use crate::models::witness_network::WitnessNetwork;
 
 #[test]
 fn parse_revision_future() {
@@ -106,6 +109,70 @@ fn parse_revision_future() {
     let _rev: Revision =
         serde_json::from_str(REV_TEST_PAGE_SIG_WIT).expect("failed to parse with sig and wit");
     //dbg!(_rev);
+    let witness = _rev.witness.clone().expect("witness not parsed");
+    witness.verify_witness_hash().expect("witness hashes rejected");
+    let mut tampered = witness.clone();
+    tampered.witness_network = WitnessNetwork::Mainnet;
+    let err = tampered.verify_witness_hash().expect_err("tampered network accepted");
+    assert_eq!(err.0.len(), 1);
+    assert_eq!(err.0[0].0, crate::models::witness::WitnessHashField::WitnessHash);
+
+    // All witness fields survive a round trip. Addresses are compared as values, as they
+    // are written in their EIP-55 checksum case rather than the case they were read in.
+    let original: serde_json::Value = serde_json::from_str(REV_TEST_PAGE_SIG_WIT).unwrap();
+    let original = &original["witness"];
+    let written = serde_json::to_value(&witness).expect("failed to write witness");
+    let (original, written) = (original.as_object().unwrap(), written.as_object().unwrap());
+    assert_eq!(
+        original.keys().collect::<std::collections::BTreeSet<_>>(),
+        written.keys().collect()
+    );
+    for (key, value) in original {
+        match key.as_str() {
+            "smart_contract_address" | "sender_account_address" => {
+                let address =
+                    |value: &serde_json::Value| value.as_str().unwrap().parse::<ethaddr::Address>().unwrap();
+                assert_eq!(address(value), address(&written[key]), "{key} changed");
+            }
+            _ => assert_eq!(value, &written[key], "{key} changed"),
+        }
+    }
+    assert_eq!(witness.source, Some(crate::models::witness::WitnessSource::Imported));
+    let reread: crate::models::witness::RevisionWitness =
+        serde_json::from_value(serde_json::Value::Object(written.clone())).unwrap();
+    assert_eq!(reread, witness);
+    // Witnesses written before these fields existed still parse.
+    let mut legacy = written.clone();
+    for key in [
+        "witness_event_id",
+        "domain_id",
+        "domain_snapshot_title",
+        "smart_contract_address",
+        "sender_account_address",
+        "source",
+    ] {
+        legacy.remove(key);
+    }
+    let legacy: crate::models::witness::RevisionWitness =
+        serde_json::from_value(serde_json::Value::Object(legacy)).expect("legacy witness rejected");
+    assert_eq!((legacy.source, legacy.sender_account_address), (None, None));
+    legacy.verify_witness_hash().expect("legacy witness hashes rejected");
+    // Goerli has been shut down; its witnesses are only rejected on request.
+    let report = crate::verify::verify_revision(&_rev, None);
+    assert_eq!(report.witness_hash, crate::verify::CheckStatus::Passed);
+    assert_eq!(report.witness_network, crate::verify::CheckStatus::Skipped);
+    let options = crate::verify::VerifyOptions {
+        witness_networks: Some(vec![WitnessNetwork::Mainnet, WitnessNetwork::Sepolia]),
+    };
+    let report = crate::verify::verify_revision_with(&_rev, None, &options);
+    assert!(matches!(report.witness_network, crate::verify::CheckStatus::Failed(_)));
+    let options = crate::verify::VerifyOptions {
+        witness_networks: Some(vec![WitnessNetwork::Goerli]),
+    };
+    let report = crate::verify::verify_revision_with(&_rev, None, &options);
+    assert_eq!(report.witness_network, crate::verify::CheckStatus::Passed);
+    // Legacy proofs omit the successor of their nodes.
+    assert!(witness.structured_merkle_proof[0].successor.is_none());
 }
 
 // #[test]
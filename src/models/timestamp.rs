@@ -6,7 +6,7 @@ const FORMAT: &str = "%Y%m%d%H%M%S";
 /// A wrapper for `chrono::NaiveDateTime` to handle timestamp formatting and parsing.
 ///
 /// Timestamps are serialized and deserialized using the format `"%Y%m%d%H%M%S"`.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Timestamp(chrono::NaiveDateTime);
 
 impl From<chrono::NaiveDateTime> for Timestamp {
@@ -0,0 +1,52 @@
+//! Typed references of the `transclusion-hashes` content slot.
+//!
+//! The slot holds a JSON list (inside a string) of the pages transcluded into `main`,
+//! each pinned to a `verification_hash` or `null` if the page was not verified yet.
+
+use crate::models::hash::Hash;
+
+/// A page transcluded into a revision.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Transclusion {
+    /// Title of the transcluded page, with underscores instead of spaces.
+    pub dbkey: String,
+    /// MediaWiki namespace of the transcluded page.
+    pub ns: i64,
+    /// Revision of the page that was transcluded, `None` if it is not verified.
+    pub verification_hash: Option<Hash>,
+}
+
+/// Parses the value of a `transclusion-hashes` slot.
+pub fn parse_transclusions(raw: &str) -> Result<Vec<Transclusion>, serde_json::Error> {
+    serde_json::from_str(raw)
+}
+
+/// Writes transclusions as the value of a `transclusion-hashes` slot.
+pub fn write_transclusions(transclusions: &[Transclusion]) -> String {
+    // Serializing strings, integers and hashes cannot fail.
+    serde_json::to_string(transclusions).expect("transclusions are always serializable")
+}
+
+#[test]
+fn read_write_transclusions() {
+    let rev = crate::tests::fixtures::receiver();
+    let raw = rev
+        .content
+        .content
+        .transclusion_hashes()
+        .expect("fixture has transclusions");
+
+    let transclusions = parse_transclusions(raw).expect("failed to parse transclusions");
+    assert_eq!(transclusions.len(), 2);
+    assert_eq!(transclusions[0].dbkey, "DataAccessAgreement");
+    assert_eq!(transclusions[0].ns, 10);
+    assert!(transclusions.iter().all(|t| t.verification_hash.is_some()));
+    assert_eq!(write_transclusions(&transclusions), raw);
+
+    let unpinned =
+        parse_transclusions(r#"[{"dbkey":"Aqua_Protocol","ns":0,"verification_hash":null}]"#)
+            .expect("failed to parse unpinned transclusion");
+    assert_eq!(unpinned[0].verification_hash, None);
+    parse_transclusions(r#"[{"dbkey":"Aqua_Protocol","ns":0,"verification_hash":"abc"}]"#)
+        .expect_err("invalid verification_hash accepted");
+}
@@ -1,7 +1,8 @@
 //! Defines the `TxHash` struct, which represents a transaction hash as a 32-byte array.
 
 
-use super::stack_str::{from_hex, StackStr};
+use super::stack_str::StackStr;
+use crate::error::{read_prefixed_hex, ReadError};
 
 /// Represents a transaction hash as a 32-byte array.
 ///
@@ -12,6 +13,7 @@ use super::stack_str::{from_hex, StackStr};
 /// # Example
 /// ```rust
 /// use std::str::FromStr;
+/// use aqua_verifier_rs_types::models::tx_hash::TxHash;
 ///
 /// let tx_hash: TxHash = TxHash::from_str("0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef").unwrap();
 /// println!("{}", tx_hash);
@@ -52,47 +54,24 @@ impl TxHash {
 // }
 
 impl std::str::FromStr for TxHash {
-    type Err = String;
+    type Err = ReadError;
 
     /// Parses a hexadecimal string into a `TxHash`.
     ///
     /// # Parameters
-    /// - `s`: A string containing the transaction hash, with or without the "0x" prefix.
+    /// - `s`: A lowercase string containing the transaction hash with the "0x" prefix.
     ///
     /// # Returns
     /// - `Ok(TxHash)`: If the input string is a valid 64-character hex string.
-    /// - `Err(String)`: If the input string is invalid or of incorrect length.
+    /// - `Err(ReadError)`: If the input string is invalid or of incorrect length.
     ///
     /// # Errors
-    /// - `"HASH HAS NO '0x' PREFIX"`: If the input lacks the "0x" prefix.
-    /// - `"LENGTH NOT EQUAL TO 64"`: If the hex string is not exactly 64 characters.
-    /// - `"UNABLE TO DECODE"`: If the hex string cannot be decoded.
+    /// - [`ReadError::NoPrefix`]: If the input lacks the "0x" prefix.
+    /// - [`ReadError::NotHex`]: If the input contains a character that is not a hex digit.
+    /// - [`ReadError::NotLowercase`]: If the input contains uppercase characters.
+    /// - [`ReadError::WrongLength`]: If the hex string is not exactly 64 characters.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = if s.starts_with("0x") {
-            // If the string starts with "0x", just use it as is
-            s
-        } else {
-            // If the string doesn't start with "0x", add it manually
-            let mut prefixed = String::with_capacity(2 + s.len());
-            prefixed.push_str("0x");
-            prefixed.push_str(s);
-            &prefixed.clone()
-        };
-
-        // Strip the "0x" now, and handle the rest as a hex string
-        let s = s
-            .strip_prefix("0x")
-            .ok_or("HASH HAS NO '0x' PREFIX".to_string())?;
-
-        // Ensure the hex string is the correct length (64 characters for 32 bytes)
-        if s.len() != 64 {
-            return Err("LENGTH NOT EQUAL TO 64".to_string());
-        }
-
-        // Decode the hex string into bytes
-        let mut bytes = [0u8; 32];
-        hex::decode_to_slice(s, &mut bytes).map_err(|_| "UNABLE TO DECODE".to_string())?;
-        Ok(TxHash(bytes))
+        read_prefixed_hex(s).map(TxHash)
     }
 }
 
@@ -139,7 +118,7 @@ impl<'de> serde::Deserialize<'de> for TxHash {
     {
         let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
         s.parse()
-            .map_err(|_| serde::de::Error::custom("Invalid sha3_512 hash"))
+            .map_err(|e| serde::de::Error::custom(format!("Invalid transaction hash: {e}")))
     }
 }
 
@@ -175,6 +154,15 @@ fn test_read() {
     const TEST_DATA_WITH_UPPER: &str = "0x17cb36e3abfe5cd2894f7b324102C3864d202Bc7b85e4f3e5ec78ca2c3db79d7";
     <TxHash as std::str::FromStr>::from_str(TEST_DATA_WITH_UPPER)
         .expect_err("Accepted TxHash wiTh miXed caSe.");
+    assert_eq!(
+        TEST_DATA_WITH_UPPER.parse::<TxHash>(),
+        Err(ReadError::NotLowercase { offset: 30 })
+    );
+    assert_eq!(TEST_DATA_NOPREFIX.parse::<TxHash>(), Err(ReadError::NoPrefix));
+    assert_eq!(
+        "0x17cb36e3".parse::<TxHash>(),
+        Err(ReadError::WrongLength { expected: 64, found: 8 })
+    );
 }
 
 #[test]
@@ -2,12 +2,32 @@
 //! and the `RevisionWitness` struct, which contains the information stored on the blockchain.
 
 
-use crate::models::hash::Hash;
+use ethaddr::Address;
+use sha3::Digest;
+
+use crate::models::hash::{display_mismatches, Hash, HashMismatch};
 use crate::models::tx_hash::TxHash;
+use crate::models::witness_network::WitnessNetwork;
 
 /// Contains the information stored on the blockchain
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+///
+/// The fields describing the witness event but not covered by its hashes are optional,
+/// as older witnesses lack them. Addresses are written in their EIP-55 checksum case,
+/// whatever case they were read in: a round trip keeps their value, not their text.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct RevisionWitness {
+     /// Identifier of the witness event.
+     #[serde(default, skip_serializing_if = "Option::is_none")]
+     pub witness_event_id: Option<u64>,
+
+     /// Domain the witness event belongs to, `null` for imported witnesses.
+     #[serde(default)]
+     pub domain_id: Option<String>,
+
+     /// Title of the domain snapshot page.
+     #[serde(default, skip_serializing_if = "Option::is_none")]
+     pub domain_snapshot_title: Option<String>,
+
      /// Hash representing the genesis state of the domain snapshot.
      pub domain_snapshot_genesis_hash: Hash,
 
@@ -15,10 +35,22 @@ pub struct RevisionWitness {
      pub merkle_root: Hash,
  
      /// Identifier for the network that recorded this witness.
-     pub witness_network: String,
+     pub witness_network: WitnessNetwork,
+
+     /// Address of the witness smart contract the transaction was sent to.
+     #[serde(default, skip_serializing_if = "Option::is_none")]
+     pub smart_contract_address: Option<Address>,
  
      /// Transaction hash where the witness event is recorded.
      pub witness_event_transaction_hash: TxHash,
+
+     /// Address of the account that sent the witness transaction.
+     #[serde(default, skip_serializing_if = "Option::is_none")]
+     pub sender_account_address: Option<Address>,
+
+     /// Whether the witness was recorded locally or imported.
+     #[serde(default, skip_serializing_if = "Option::is_none")]
+     pub source: Option<WitnessSource>,
  
      /// Verification hash for the witness event.
      pub witness_event_verification_hash: Hash,
@@ -30,8 +62,18 @@ pub struct RevisionWitness {
      pub structured_merkle_proof: Vec<MerkleNode>,
 }
 
+/// Origin of a witness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WitnessSource {
+    /// The witness was imported from another domain.
+    Imported,
+    /// The witness was recorded by this domain.
+    Local,
+}
+
 /// Represents a single node in the Merkle tree.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct MerkleNode {
     /// The hash of the left child (leaf or node).
     pub left_leaf: Hash,
@@ -40,5 +82,442 @@ pub struct MerkleNode {
     pub right_leaf: Hash,
 
     /// The resulting hash after combining `left_leaf` and `right_leaf`.
-    pub successor: Hash,
+    /// Legacy proofs omit it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<Hash>,
+}
+
+impl MerkleNode {
+    /// Computes the successor of the node: the SHA3-512 digest of the
+    /// hex-encoded `left_leaf` and `right_leaf`.
+    pub fn compute_successor(&self) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        hasher.update(self.left_leaf.to_stackstr().as_bytes());
+        hasher.update(self.right_leaf.to_stackstr().as_bytes());
+        Hash::from(hasher.finalize())
+    }
+}
+
+impl RevisionWitness {
+    /// Computes the `witness_hash`: the SHA3-512 digest of `domain_snapshot_genesis_hash`,
+    /// `merkle_root`, `witness_network` and `witness_event_transaction_hash`.
+    pub fn compute_witness_hash(&self) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        hasher.update(self.domain_snapshot_genesis_hash.to_stackstr().as_bytes());
+        hasher.update(self.merkle_root.to_stackstr().as_bytes());
+        hasher.update(self.witness_network.as_str().as_bytes());
+        hasher.update(self.witness_event_transaction_hash.to_stackstr().as_bytes());
+        Hash::from(hasher.finalize())
+    }
+
+    /// Computes the `witness_event_verification_hash`: the SHA3-512 digest of
+    /// `domain_snapshot_genesis_hash` and `merkle_root`.
+    pub fn compute_witness_event_verification_hash(&self) -> Hash {
+        let mut hasher = crate::crypt::Hasher::default();
+        hasher.update(self.domain_snapshot_genesis_hash.to_stackstr().as_bytes());
+        hasher.update(self.merkle_root.to_stackstr().as_bytes());
+        Hash::from(hasher.finalize())
+    }
+
+    /// Checks `witness_event_verification_hash` and `witness_hash` against recomputed values.
+    ///
+    /// # Errors
+    /// Returns a [`WitnessHashError`] listing every stored hash that disagrees.
+    pub fn verify_witness_hash(&self) -> Result<(), WitnessHashError> {
+        let mut mismatches = Vec::new();
+        let computed = self.compute_witness_event_verification_hash();
+        if computed != self.witness_event_verification_hash {
+            mismatches.push((
+                WitnessHashField::WitnessEventVerificationHash,
+                HashMismatch { expected: self.witness_event_verification_hash, computed },
+            ));
+        }
+        let computed = self.compute_witness_hash();
+        if computed != self.witness_hash {
+            mismatches.push((
+                WitnessHashField::WitnessHash,
+                HashMismatch { expected: self.witness_hash, computed },
+            ));
+        }
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(WitnessHashError(mismatches))
+        }
+    }
+
+    /// Verifies that `leaf` is included in the tree with root `merkle_root`.
+    ///
+    /// `leaf` must be one of the leaves of the first node, and every following node
+    /// must contain the successor of the node before it. Nodes of legacy proofs without
+    /// a `successor` are checked against the computed successor only. An empty proof
+    /// stands for a tree with the single leaf `merkle_root`.
+    ///
+    /// # Errors
+    /// Returns a [`MerkleProofError`] describing the first failing node.
+    pub fn verify_merkle_proof(&self, leaf: Hash) -> Result<(), MerkleProofError> {
+        let mut expected_leaf = leaf;
+        for (index, node) in self.structured_merkle_proof.iter().enumerate() {
+            if node.left_leaf != expected_leaf && node.right_leaf != expected_leaf {
+                return Err(if index == 0 {
+                    MerkleProofError::LeafNotFound
+                } else {
+                    MerkleProofError::NotLinked { index }
+                });
+            }
+            let computed = node.compute_successor();
+            if let Some(expected) = node.successor {
+                if expected != computed {
+                    return Err(MerkleProofError::SuccessorMismatch {
+                        index,
+                        mismatch: Box::new(HashMismatch { expected, computed }),
+                    });
+                }
+            }
+            expected_leaf = computed;
+        }
+        if expected_leaf != self.merkle_root {
+            return Err(MerkleProofError::RootMismatch(Box::new(HashMismatch {
+                expected: self.merkle_root,
+                computed: expected_leaf,
+            })));
+        }
+        Ok(())
+    }
+
+    /// Verifies that `leaf` is the leaf at `index` of a tree with `leaf_count` leaves
+    /// and root `merkle_root`.
+    ///
+    /// In addition to [`RevisionWitness::verify_merkle_proof`], the proof must have one
+    /// node per level of the tree on which the hash carried up from `leaf` has a sibling
+    /// (see [`MerkleTree`]), and that hash must be the left leaf of a node on an even
+    /// position and the right leaf on an odd one.
+    ///
+    /// # Errors
+    /// Returns a [`MerkleProofError`] describing the first failing node.
+    pub fn verify_merkle_proof_at(
+        &self,
+        leaf: Hash,
+        index: usize,
+        leaf_count: usize,
+    ) -> Result<(), MerkleProofError> {
+        if index >= leaf_count {
+            return Err(MerkleProofError::IndexOutOfRange { index, leaf_count });
+        }
+        self.verify_merkle_proof(leaf)?;
+        let proof = &self.structured_merkle_proof;
+        let (mut position, mut width, mut expected) = (index, leaf_count, leaf);
+        let mut depth = 0;
+        while width > 1 {
+            let promoted = width % 2 == 1 && position == width - 1;
+            if !promoted {
+                if let Some(node) = proof.get(depth) {
+                    let carried = match position % 2 {
+                        0 => node.left_leaf,
+                        _ => node.right_leaf,
+                    };
+                    if carried != expected {
+                        return Err(MerkleProofError::WrongPosition { index: depth });
+                    }
+                    expected = node.compute_successor();
+                }
+                depth += 1;
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        if proof.len() != depth {
+            return Err(MerkleProofError::WrongDepth {
+                expected: depth,
+                found: proof.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A Merkle tree over the verification hashes of a batch of revisions, used to
+/// witness all of them with a single transaction.
+///
+/// Nodes hash their children like [`MerkleNode::compute_successor`]. A node without
+/// a sibling is promoted to the next level unchanged, rather than paired with itself,
+/// so `[a, b, c]` and `[a, b, c, c]` have different roots. The root of a single-leaf
+/// tree is the leaf, with an empty proof.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// All levels of the tree, from the leaves up to the root.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl MerkleTree {
+    /// Builds the tree over `leaves`.
+    ///
+    /// # Returns
+    /// `None` if `leaves` is empty.
+    pub fn new(leaves: impl IntoIterator<Item = Hash>) -> Option<Self> {
+        let mut levels = vec![leaves.into_iter().collect::<Vec<_>>()];
+        if levels[0].is_empty() {
+            return None;
+        }
+        while levels.last().unwrap().len() > 1 {
+            let next: Vec<Hash> = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => Self::node(*left, *right).compute_successor(),
+                    _ => pair[0],
+                })
+                .collect();
+            levels.push(next);
+        }
+        Some(MerkleTree { levels })
+    }
+
+    /// The leaves the tree was built from.
+    pub fn leaves(&self) -> &[Hash] {
+        &self.levels[0]
+    }
+
+    /// The root of the tree.
+    pub fn merkle_root(&self) -> Hash {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// The structured Merkle proof of the leaf at `index`, from the leaf up to the root.
+    ///
+    /// # Returns
+    /// `None` if `index` is out of bounds.
+    pub fn proof(&self, mut index: usize) -> Option<Vec<MerkleNode>> {
+        if index >= self.leaves().len() {
+            return None;
+        }
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            let start = index - index % 2;
+            if let [left, right] = level[start..(start + 2).min(level.len())] {
+                let mut node = Self::node(left, right);
+                node.successor = Some(node.compute_successor());
+                proof.push(node);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+
+    /// The structured Merkle proof of the first leaf equal to `leaf`.
+    pub fn proof_for(&self, leaf: &Hash) -> Option<Vec<MerkleNode>> {
+        self.proof(self.leaves().iter().position(|l| l == leaf)?)
+    }
+
+    fn node(left_leaf: Hash, right_leaf: Hash) -> MerkleNode {
+        MerkleNode {
+            left_leaf,
+            right_leaf,
+            successor: None,
+        }
+    }
+}
+
+/// The hashes stored in [`RevisionWitness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessHashField {
+    /// `witness_event_verification_hash`, over the domain snapshot and the Merkle root.
+    WitnessEventVerificationHash,
+    /// `witness_hash`, over the domain snapshot, the Merkle root, the network and the
+    /// transaction.
+    WitnessHash,
+}
+
+impl std::fmt::Display for WitnessHashField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WitnessHashField::WitnessEventVerificationHash => "witness_event_verification_hash",
+            WitnessHashField::WitnessHash => "witness_hash",
+        })
+    }
+}
+
+/// The stored hashes of a [`RevisionWitness`] that disagree with the recomputed ones.
+#[derive(thiserror::Error, Debug)]
+#[error("{}", display_mismatches(.0))]
+pub struct WitnessHashError(pub Vec<(WitnessHashField, HashMismatch)>);
+
+/// Error types for [`RevisionWitness::verify_merkle_proof`].
+#[derive(thiserror::Error, Debug)]
+pub enum MerkleProofError {
+    /// The leaf is neither the left nor the right leaf of the first node.
+    #[error("leaf is not part of the first merkle node")]
+    LeafNotFound,
+
+    /// The node does not contain the successor of the node before it.
+    #[error("merkle node {index} does not contain the successor of node {}", .index - 1)]
+    NotLinked { index: usize },
+
+    /// The stored successor of the node does not match its leaves.
+    #[error("merkle node {index}: successor mismatch: {mismatch}")]
+    SuccessorMismatch { index: usize, mismatch: Box<HashMismatch> },
+
+    /// The last successor is not the `merkle_root`.
+    #[error("merkle root mismatch: {0}")]
+    RootMismatch(Box<HashMismatch>),
+
+    /// The leaf index is not below the number of leaves.
+    #[error("leaf {index} is out of range for {leaf_count} leaves")]
+    IndexOutOfRange { index: usize, leaf_count: usize },
+
+    /// The node holds the hash carried up from the leaf on the wrong side.
+    #[error("merkle node {index} holds the leaf on the wrong side")]
+    WrongPosition { index: usize },
+
+    /// The proof does not have one node per level of the tree.
+    #[error("merkle proof has {found} nodes, the tree has {expected} levels")]
+    WrongDepth { expected: usize, found: usize },
+}
+
+#[cfg(test)]
+fn test_witness(structured_merkle_proof: Vec<MerkleNode>, merkle_root: Hash) -> RevisionWitness {
+    RevisionWitness {
+        witness_event_id: None,
+        domain_id: None,
+        domain_snapshot_title: None,
+        domain_snapshot_genesis_hash: Hash::default(),
+        merkle_root,
+        witness_network: WitnessNetwork::Sepolia,
+        smart_contract_address: None,
+        witness_event_transaction_hash: TxHash::default(),
+        sender_account_address: None,
+        source: None,
+        witness_event_verification_hash: Hash::default(),
+        witness_hash: Hash::default(),
+        structured_merkle_proof,
+    }
+}
+
+#[test]
+fn verify_merkle_proof() {
+    let leaf = Hash::from([1; 64]);
+    let sibling = Hash::from([2; 64]);
+    let uncle = Hash::from([3; 64]);
+    let mut first = MerkleNode { left_leaf: leaf, right_leaf: sibling, successor: None };
+    let parent = first.compute_successor();
+    first.successor = Some(parent);
+    let mut second = MerkleNode { left_leaf: uncle, right_leaf: parent, successor: None };
+    let root = second.compute_successor();
+    second.successor = Some(root);
+
+    let witness = test_witness(vec![first.clone(), second.clone()], root);
+    witness.verify_merkle_proof(leaf).expect("valid proof rejected");
+    witness.verify_merkle_proof(sibling).expect("valid proof rejected");
+    assert!(matches!(witness.verify_merkle_proof(uncle), Err(MerkleProofError::LeafNotFound)));
+
+    let legacy = test_witness(
+        vec![
+            MerkleNode { successor: None, ..first.clone() },
+            MerkleNode { successor: None, ..second.clone() },
+        ],
+        root,
+    );
+    legacy.verify_merkle_proof(leaf).expect("valid legacy proof rejected");
+
+    let mut broken = witness.clone();
+    broken.structured_merkle_proof[1].successor = Some(leaf);
+    assert!(matches!(
+        broken.verify_merkle_proof(leaf),
+        Err(MerkleProofError::SuccessorMismatch { index: 1, .. })
+    ));
+    broken.structured_merkle_proof[1] = MerkleNode {
+        left_leaf: uncle,
+        right_leaf: uncle,
+        successor: None,
+    };
+    assert!(matches!(
+        broken.verify_merkle_proof(leaf),
+        Err(MerkleProofError::NotLinked { index: 1 })
+    ));
+    assert!(matches!(
+        test_witness(vec![], root).verify_merkle_proof(leaf),
+        Err(MerkleProofError::RootMismatch(_))
+    ));
+    test_witness(vec![], leaf).verify_merkle_proof(leaf).expect("single leaf rejected");
+}
+
+#[test]
+fn verify_merkle_proof_fixture() {
+    let rev: crate::models::revision::Revision =
+        serde_json::from_str(include_str!("../tests/test_data/DAA_SIG_SENDER_WIT.json"))
+            .expect("failed to parse");
+    let witness = rev.witness.expect("witness not parsed");
+    let leaf = rev.metadata.verification_hash;
+    witness.verify_witness_hash().expect("witness hashes rejected");
+    witness.verify_merkle_proof(leaf).expect("valid proof rejected");
+    witness
+        .verify_merkle_proof_at(leaf, 2, 4)
+        .expect("valid proof rejected at its position");
+
+    for (index, leaf_count, node) in [(3, 4, 0), (1, 4, 0), (0, 4, 1), (2, 3, 0)] {
+        assert!(matches!(
+            witness.verify_merkle_proof_at(leaf, index, leaf_count),
+            Err(MerkleProofError::WrongPosition { index }) if index == node
+        ));
+    }
+    assert!(matches!(
+        witness.verify_merkle_proof_at(leaf, 2, 8),
+        Err(MerkleProofError::WrongDepth { expected: 3, found: 2 })
+    ));
+    assert!(matches!(
+        witness.verify_merkle_proof_at(leaf, 4, 4),
+        Err(MerkleProofError::IndexOutOfRange { .. })
+    ));
+    assert!(matches!(
+        witness.verify_merkle_proof(rev.metadata.metadata_hash),
+        Err(MerkleProofError::LeafNotFound)
+    ));
+}
+
+#[test]
+fn merkle_tree_proofs() {
+    assert!(MerkleTree::new([]).is_none());
+
+    for size in 1..=9u8 {
+        let leaves: Vec<Hash> = (0..size).map(|i| Hash::from([i; 64])).collect();
+        let tree = MerkleTree::new(leaves.clone()).unwrap();
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            let witness = test_witness(proof, tree.merkle_root());
+            witness
+                .verify_merkle_proof_at(*leaf, index, size as usize)
+                .unwrap_or_else(|e| panic!("leaf {index} of {size}: {e}"));
+        }
+        assert!(tree.proof(size as usize).is_none());
+    }
+
+    let leaf = Hash::from([42; 64]);
+    let tree = MerkleTree::new([leaf]).unwrap();
+    assert_eq!(tree.proof_for(&leaf).unwrap(), []);
+    assert_eq!(tree.merkle_root(), leaf);
+}
+
+#[test]
+fn merkle_tree_odd_leaf() {
+    let [a, b, c] = [1, 2, 3].map(|i| Hash::from([i; 64]));
+    let odd = MerkleTree::new([a, b, c]).unwrap();
+    let duplicated = MerkleTree::new([a, b, c, c]).unwrap();
+    assert_ne!(odd.merkle_root(), duplicated.merkle_root());
+
+    // `c` is promoted, its proof only pairs it with the node over `a` and `b`.
+    let proof = odd.proof(2).unwrap();
+    assert_eq!(proof.len(), 1);
+    assert_eq!(proof[0].right_leaf, c);
+    let witness = test_witness(proof, odd.merkle_root());
+    witness.verify_merkle_proof_at(c, 2, 3).expect("promoted leaf rejected");
+    assert!(matches!(
+        witness.verify_merkle_proof_at(c, 2, 4),
+        Err(MerkleProofError::WrongPosition { index: 0 })
+    ));
+    let duplicate = test_witness(duplicated.proof(3).unwrap(), odd.merkle_root());
+    assert!(matches!(
+        duplicate.verify_merkle_proof(c),
+        Err(MerkleProofError::RootMismatch(_))
+    ));
 }
@@ -0,0 +1,133 @@
+//! Defines the `WitnessNetwork` enum, identifying the Ethereum network a witness was recorded on.
+
+use ethaddr::Address;
+
+/// Address of the Aqua witness smart contract on goerli, as recorded in the
+/// `smart_contract_address` of the goerli witnesses exported by PKC (see the witness
+/// fixtures in `models/tests.rs` and `models/revision.rs`).
+const GOERLI_WITNESS_CONTRACT: Address =
+    ethaddr::address!("0x45f59310ADD88E6d23ca58A0Fa7A55BEE6d2a611");
+
+/// The Ethereum network a witness was recorded on.
+///
+/// Serialized as the network name used in `witness_network` (e.g. `"sepolia"`).
+/// Unknown names are kept verbatim in [`WitnessNetwork::Custom`], so the `witness_hash`
+/// of a revision stays reproducible.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WitnessNetwork {
+    /// Ethereum mainnet.
+    Mainnet,
+    /// The Sepolia testnet.
+    Sepolia,
+    /// The Holesky testnet.
+    Holesky,
+    /// The Goerli testnet, shut down in 2024.
+    Goerli,
+    /// Any other network, by name.
+    Custom(String),
+}
+
+impl WitnessNetwork {
+    /// The name of the network, as written in `witness_network`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            WitnessNetwork::Mainnet => "mainnet",
+            WitnessNetwork::Sepolia => "sepolia",
+            WitnessNetwork::Holesky => "holesky",
+            WitnessNetwork::Goerli => "goerli",
+            WitnessNetwork::Custom(name) => name,
+        }
+    }
+
+    /// The EIP-155 chain ID of the network, `None` for custom networks.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            WitnessNetwork::Mainnet => Some(1),
+            WitnessNetwork::Sepolia => Some(11_155_111),
+            WitnessNetwork::Holesky => Some(17_000),
+            WitnessNetwork::Goerli => Some(5),
+            WitnessNetwork::Custom(_) => None,
+        }
+    }
+
+    /// Address of the Aqua witness smart contract on the network, if a deployment is
+    /// known. Only the goerli contract is, other networks have no default.
+    pub fn default_contract_address(&self) -> Option<Address> {
+        match self {
+            WitnessNetwork::Goerli => Some(GOERLI_WITNESS_CONTRACT),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for networks that are no longer operated, whose witnesses can
+    /// therefore not be checked anymore. Verifiers may choose to reject them through
+    /// [`VerifyOptions`](crate::verify::VerifyOptions).
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self, WitnessNetwork::Goerli)
+    }
+}
+
+impl std::str::FromStr for WitnessNetwork {
+    type Err = std::convert::Infallible;
+
+    /// Parses a network name. Names other than the known lowercase ones become
+    /// [`WitnessNetwork::Custom`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "mainnet" => WitnessNetwork::Mainnet,
+            "sepolia" => WitnessNetwork::Sepolia,
+            "holesky" => WitnessNetwork::Holesky,
+            "goerli" => WitnessNetwork::Goerli,
+            _ => WitnessNetwork::Custom(s.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for WitnessNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for WitnessNetwork {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+impl serde::Serialize for WitnessNetwork {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[test]
+fn read_write_network() {
+    for name in [
+        "mainnet",
+        "sepolia",
+        "holesky",
+        "goerli",
+        "Sepolia",
+        "my-devnet",
+    ] {
+        let network: WitnessNetwork = serde_json::from_value(name.into()).unwrap();
+        assert_eq!(serde_json::to_value(&network).unwrap(), name);
+    }
+    let network: WitnessNetwork = "sepolia".parse().unwrap();
+    assert_eq!(network.chain_id(), Some(11_155_111));
+    assert!(!network.is_deprecated());
+    assert_eq!(network.default_contract_address(), None);
+    let network: WitnessNetwork = "goerli".parse().unwrap();
+    assert!(network.is_deprecated());
+    let network: WitnessNetwork = "Sepolia".parse().unwrap();
+    assert_eq!(network, WitnessNetwork::Custom("Sepolia".to_string()));
+    assert_eq!(network.default_contract_address(), None);
+}
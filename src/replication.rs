@@ -0,0 +1,419 @@
+//! Replication of revisions between two storages.
+//!
+//! [`sync`] copies every revision the target storage is missing, parents before
+//! children, so the target never holds a revision whose previous revision it lacks.
+//! Revisions which would fork a branch of the target are not copied but reported as a
+//! [`Divergence`], as are forks copied from the source; [`sync_with`] can also verify
+//! each revision before copying it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::models::hash::Hash;
+use crate::models::storage::Storage;
+use crate::verify::verify_revision;
+
+/// Options of [`sync_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Verify every revision with [`verify_revision`] before copying it, rejecting
+    /// invalid ones.
+    pub verify: bool,
+}
+
+/// Outcome of a [`sync`], updated after every revision while it runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Revisions stored in the target.
+    pub copied: usize,
+    /// Revisions the target already holds.
+    pub skipped: usize,
+    /// Revisions not copied as they, or a revision they follow, diverge from the target.
+    pub conflicting: usize,
+    /// Revisions not copied as they, or a revision they follow, failed verification.
+    pub rejected: usize,
+    /// The revisions diverging from the target, and the forks copied into it.
+    pub divergences: Vec<Divergence>,
+    /// The revisions which failed verification.
+    pub invalid: Vec<Hash>,
+}
+
+/// Two revisions following the same revision, at least one of them missing in the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The source and the target each hold a revision following `previous` which the
+    /// other lacks. `source` is not copied.
+    Conflict {
+        /// The genesis revision of the branch.
+        genesis: Hash,
+        /// The last revision both storages share.
+        previous: Hash,
+        /// The revision following `previous` only in the source.
+        source: Hash,
+        /// The revision following `previous` only in the target.
+        target: Hash,
+    },
+
+    /// The source holds two revisions following `previous`, neither of which the target
+    /// held before. Both are copied, so the target holds the fork as well.
+    Fork {
+        /// The genesis revision of the branch.
+        genesis: Hash,
+        /// The revision both follow.
+        previous: Hash,
+        /// The revision following `previous` copied first.
+        first: Hash,
+        /// The revision following `previous` copied after `first`.
+        second: Hash,
+    },
+}
+
+/// Why a revision was not copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Blocked {
+    Conflicting,
+    Rejected,
+}
+
+/// Where a revision sits in its branch.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    previous: Option<Hash>,
+    genesis: Hash,
+}
+
+/// Copies all revisions missing in `to` from `from`, see [`sync_with`].
+pub async fn sync<F, T>(from: &F, to: &T) -> Result<SyncReport, SyncError<F::Error, T::Error>>
+where
+    F: Storage,
+    T: Storage<Context = F::Context>,
+{
+    sync_with(from, to, SyncOptions::default(), |_| {}).await
+}
+
+/// Copies all revisions missing in `to` from `from`, with their contexts.
+///
+/// Revisions are stored parents first. A revision is not copied if the revision it
+/// follows already has another successor in `to` which `from` lacks, or if it fails
+/// verification when [`SyncOptions::verify`] is set; neither are the revisions following
+/// it. `progress` is called with the report so far after every revision.
+///
+/// Only hashes are kept in memory: the branches of both storages are followed with
+/// [`Storage::get_branch`] to order the missing revisions and find conflicts, and a
+/// revision is only read to copy it, or as the previous revision when verifying.
+///
+/// # Errors
+/// Returns the first error of either storage; the revisions copied until then stay in
+/// `to`.
+pub async fn sync_with<F, T>(
+    from: &F,
+    to: &T,
+    options: SyncOptions,
+    mut progress: impl FnMut(&SyncReport),
+) -> Result<SyncReport, SyncError<F::Error, T::Error>>
+where
+    F: Storage,
+    T: Storage<Context = F::Context>,
+{
+    let source = from.list().await.map_err(SyncError::Source)?;
+    let target = to.list().await.map_err(SyncError::Target)?;
+    let source_set: HashSet<Hash> = source.iter().copied().collect();
+    let target_set: HashSet<Hash> = target.iter().copied().collect();
+
+    let missing_hashes: Vec<Hash> = source
+        .iter()
+        .copied()
+        .filter(|hash| !target_set.contains(hash))
+        .collect();
+    let mut report = SyncReport {
+        skipped: source.len() - missing_hashes.len(),
+        ..SyncReport::default()
+    };
+    let source_links = follow_branches(from, missing_hashes.iter().rev().copied())
+        .await
+        .map_err(SyncError::Source)?;
+    // The revision each missing revision follows.
+    let missing: HashMap<Hash, Option<Hash>> = missing_hashes
+        .iter()
+        .map(|hash| (*hash, source_links[hash].previous))
+        .collect();
+    progress(&report);
+
+    // Successors of shared revisions which only the target holds.
+    let target_only: Vec<Hash> = target
+        .iter()
+        .copied()
+        .filter(|hash| !source_set.contains(hash))
+        .collect();
+    let target_links = follow_branches(to, target_only.iter().rev().copied())
+        .await
+        .map_err(SyncError::Target)?;
+    let mut target_successors: HashMap<Hash, Vec<Hash>> = HashMap::new();
+    for hash in target_only {
+        if let Some(previous) = target_links[&hash].previous {
+            target_successors.entry(previous).or_default().push(hash);
+        }
+    }
+
+    let mut blocked = HashMap::new();
+    // The first revision copied after each revision, to report forks.
+    let mut copied_successors = HashMap::new();
+    for hash in parents_first(&source, &missing) {
+        let Link { previous, genesis } = source_links[&hash];
+        let reason = match previous {
+            Some(previous) if missing.contains_key(&previous) => blocked.get(&previous).copied(),
+            Some(previous) => target_successors.get(&previous).map(|successors| {
+                report
+                    .divergences
+                    .extend(successors.iter().map(|&target| Divergence::Conflict {
+                        genesis,
+                        previous,
+                        source: hash,
+                        target,
+                    }));
+                Blocked::Conflicting
+            }),
+            None => None,
+        };
+        let outcome = match reason {
+            Some(reason) => Err(reason),
+            None => Ok(from.read(hash).await.map_err(SyncError::Source)?),
+        };
+        let outcome = match outcome {
+            Ok(rev) if options.verify => {
+                // Unless blocked, the previous revision is in `to` by now.
+                let previous_rev = match previous {
+                    Some(previous) => Some(to.read(previous).await.map_err(SyncError::Target)?),
+                    None => None,
+                };
+                if verify_revision(&rev, previous_rev.as_ref()).is_valid() {
+                    Ok(rev)
+                } else {
+                    report.invalid.push(hash);
+                    Err(Blocked::Rejected)
+                }
+            }
+            outcome => outcome,
+        };
+
+        match outcome {
+            Ok(rev) => {
+                let context = from.get_context(hash).await.map_err(SyncError::Source)?;
+                to.store(rev, context).await.map_err(SyncError::Target)?;
+                report.copied += 1;
+                if let Some(previous) = previous {
+                    match copied_successors.get(&previous) {
+                        Some(&first) => report.divergences.push(Divergence::Fork {
+                            genesis,
+                            previous,
+                            first,
+                            second: hash,
+                        }),
+                        None => {
+                            copied_successors.insert(previous, hash);
+                        }
+                    }
+                }
+            }
+            Err(reason) => {
+                match reason {
+                    Blocked::Conflicting => report.conflicting += 1,
+                    Blocked::Rejected => report.rejected += 1,
+                }
+                blocked.insert(hash, reason);
+            }
+        }
+        progress(&report);
+    }
+    Ok(report)
+}
+
+/// Follows the branches ending in `hashes` and records where each of their revisions
+/// sits. Hashes recorded with an earlier branch are skipped, so giving the latest
+/// revisions first follows each branch about once.
+async fn follow_branches<S: Storage>(
+    storage: &S,
+    hashes: impl Iterator<Item = Hash>,
+) -> Result<HashMap<Hash, Link>, S::Error> {
+    let mut links = HashMap::new();
+    for hash in hashes {
+        if links.contains_key(&hash) {
+            continue;
+        }
+        let branch = storage.get_branch(hash).await?;
+        let genesis = branch.hashes.first().copied().unwrap_or(hash);
+        let mut previous = None;
+        for current in branch.hashes {
+            links.entry(current).or_insert(Link { previous, genesis });
+            previous = Some(current);
+        }
+    }
+    Ok(links)
+}
+
+/// Orders the hashes of `missing` so each revision comes after the revision it follows,
+/// keeping the order of `source` otherwise.
+fn parents_first(source: &[Hash], missing: &HashMap<Hash, Option<Hash>>) -> Vec<Hash> {
+    let mut ordered = Vec::with_capacity(missing.len());
+    let mut placed = HashSet::new();
+    for &hash in source {
+        let mut chain = Vec::new();
+        let mut current = Some(hash);
+        while let Some(hash) = current {
+            if placed.contains(&hash) || chain.contains(&hash) {
+                break;
+            }
+            let Some(&previous) = missing.get(&hash) else {
+                break;
+            };
+            chain.push(hash);
+            current = previous;
+        }
+        for hash in chain.into_iter().rev() {
+            placed.insert(hash);
+            ordered.push(hash);
+        }
+    }
+    ordered
+}
+
+/// Error types of [`sync`].
+#[derive(thiserror::Error, Debug)]
+pub enum SyncError<S: std::error::Error, T: std::error::Error> {
+    /// Listing or reading revisions of the source storage failed.
+    #[error("reading from the source storage failed: {0}")]
+    Source(S),
+
+    /// Listing, reading or storing revisions of the target storage failed.
+    #[error("accessing the target storage failed: {0}")]
+    Target(T),
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn copy_parents_first() {
+    use crate::backends::memory::MemoryStorage;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+
+    // Children listed before their parents are copied parents first.
+    let from = MemoryStorage::new();
+    from.store(previous.clone(), "sender").await.unwrap();
+    from.store(rev, "receiver").await.unwrap();
+    from.remove(previous_hash);
+    from.store(previous, "sender").await.unwrap();
+    assert_eq!(from.list().await.unwrap(), [hash, previous_hash]);
+    let to = MemoryStorage::new();
+    let mut updates = 0;
+    let report = sync_with(&from, &to, SyncOptions { verify: true }, |_| updates += 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        SyncReport {
+            copied: 2,
+            ..SyncReport::default()
+        }
+    );
+    assert_eq!(updates, 3);
+    assert_eq!(to.list().await.unwrap(), [previous_hash, hash]);
+    assert_eq!(to.get_context(hash).await.unwrap(), "receiver");
+
+    let report = sync(&from, &to).await.unwrap();
+    assert_eq!((report.copied, report.skipped), (0, 2));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn report_conflicts() {
+    use crate::backends::memory::MemoryStorage;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let mut sibling = rev.clone();
+    sibling.metadata.verification_hash = Hash::default();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+
+    // The target holds another revision following `previous`.
+    let from = MemoryStorage::new();
+    from.store(previous.clone(), "sender").await.unwrap();
+    from.store(rev, "receiver").await.unwrap();
+    let to = MemoryStorage::new();
+    to.store(previous, "sender").await.unwrap();
+    to.store(sibling, "sibling").await.unwrap();
+    let report = sync(&from, &to).await.unwrap();
+    assert_eq!(
+        (report.copied, report.skipped, report.conflicting),
+        (0, 1, 1)
+    );
+    assert_eq!(
+        report.divergences,
+        [Divergence::Conflict {
+            genesis: previous_hash,
+            previous: previous_hash,
+            source: hash,
+            target: Hash::default(),
+        }]
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn copy_forks() {
+    use crate::backends::memory::MemoryStorage;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let mut sibling = rev.clone();
+    sibling.metadata.verification_hash = Hash::default();
+    let (previous_hash, hash) = (
+        previous.metadata.verification_hash,
+        rev.metadata.verification_hash,
+    );
+
+    // A fork only the source holds is copied and reported.
+    let from = MemoryStorage::new();
+    from.store(previous, "sender").await.unwrap();
+    from.store(rev, "receiver").await.unwrap();
+    from.store(sibling, "sibling").await.unwrap();
+    let to = MemoryStorage::new();
+    let report = sync(&from, &to).await.unwrap();
+    assert_eq!(report.copied, 3);
+    assert_eq!(
+        report.divergences,
+        [Divergence::Fork {
+            genesis: previous_hash,
+            previous: previous_hash,
+            first: hash,
+            second: Hash::default(),
+        }]
+    );
+    assert_eq!(to.list().await.unwrap().len(), 3);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn reject_invalid_revisions() {
+    use crate::backends::memory::MemoryStorage;
+
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let mut sibling = rev;
+    sibling.metadata.verification_hash = Hash::default();
+    let previous_hash = previous.metadata.verification_hash;
+
+    // `sibling` does not match its `verification_hash`.
+    let from = MemoryStorage::new();
+    from.store(previous, "sender").await.unwrap();
+    from.store(sibling, "sibling").await.unwrap();
+    let to = MemoryStorage::new();
+    let report = sync_with(&from, &to, SyncOptions { verify: true }, |_| {})
+        .await
+        .unwrap();
+    assert_eq!((report.copied, report.rejected), (1, 1));
+    assert_eq!(report.invalid, [Hash::default()]);
+    assert_eq!(to.list().await.unwrap(), [previous_hash]);
+}
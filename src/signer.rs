@@ -0,0 +1,107 @@
+//! Creation of revision signatures.
+//!
+//! A [`Signer`] produces the [`RevisionSignature`] of a revision. [`LocalKeySigner`]
+//! signs headlessly with a secp256k1 secret key, producing the same `personal_sign`
+//! (EIP-191) signatures as a browser wallet.
+
+use ethaddr::Address;
+
+use crate::models::hash::Hash;
+use crate::models::public_key::PublicKey;
+use crate::models::revision::Revision;
+use crate::models::signature::{personal_message_hash, RevisionSignature, Signature};
+
+/// Trait for anything able to sign revisions.
+pub trait Signer {
+    /// Type of error returned when signing fails.
+    type Error: std::error::Error;
+
+    /// Signs the revision with the given `verification_hash`.
+    ///
+    /// # Returns
+    /// A complete [`RevisionSignature`], including `signature_hash` and `wallet_address`.
+    fn sign_hash(&self, verification_hash: &Hash) -> Result<RevisionSignature, Self::Error>;
+
+    /// Signs `rev` over its `verification_hash`.
+    fn sign(&self, rev: &Revision) -> Result<RevisionSignature, Self::Error> {
+        self.sign_hash(&rev.metadata.verification_hash)
+    }
+}
+
+/// A [`Signer`] backed by a secp256k1 secret key held in memory.
+#[derive(Clone)]
+pub struct LocalKeySigner {
+    secret_key: libsecp256k1::SecretKey,
+    public_key: PublicKey,
+}
+
+impl LocalKeySigner {
+    /// Creates a signer for the given secret key.
+    pub fn new(secret_key: libsecp256k1::SecretKey) -> Self {
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key).into();
+        LocalKeySigner { secret_key, public_key }
+    }
+
+    /// Creates a signer with a freshly generated secret key.
+    pub fn random() -> Self {
+        Self::new(libsecp256k1::SecretKey::random(&mut rand::thread_rng()))
+    }
+
+    /// The public key of the signer.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// The Ethereum wallet address of the signer.
+    pub fn wallet_address(&self) -> Address {
+        self.public_key.into()
+    }
+}
+
+/// Does not print the secret key.
+impl std::fmt::Debug for LocalKeySigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalKeySigner")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signer for LocalKeySigner {
+    type Error = std::convert::Infallible;
+
+    fn sign_hash(&self, verification_hash: &Hash) -> Result<RevisionSignature, Self::Error> {
+        let message = libsecp256k1::Message::parse(&personal_message_hash(verification_hash));
+        let signature = Signature::from(libsecp256k1::sign(&message, &self.secret_key));
+        let mut rev_signature = RevisionSignature {
+            signature,
+            public_key: self.public_key,
+            signature_hash: Hash::default(),
+            wallet_address: self.wallet_address(),
+        };
+        rev_signature.signature_hash = rev_signature.compute_signature_hash();
+        Ok(rev_signature)
+    }
+}
+
+#[test]
+fn sign_and_verify() {
+    let signer = LocalKeySigner::new(libsecp256k1::SecretKey::parse(&[7; 32]).unwrap());
+    let mut rev = crate::tests::fixtures::sender();
+    let signature = signer.sign(&rev).unwrap();
+    signature
+        .verify(&rev.metadata.verification_hash)
+        .expect("own signature rejected");
+
+    let encoded = <[u8; 65]>::from(signature.signature);
+    assert!(encoded[64] == 27 || encoded[64] == 28);
+
+    rev.signature = Some(signature);
+    let json = serde_json::to_string(&rev).expect("failed to serialize");
+    let parsed: Revision = serde_json::from_str(&json).expect("failed to parse own signature");
+    parsed
+        .signature
+        .unwrap()
+        .verify(&rev.metadata.verification_hash)
+        .expect("round tripped signature rejected");
+}
@@ -0,0 +1,32 @@
+//! Revisions of the `test_data` files, shared by the tests.
+
+use crate::models::revision::Revision;
+
+/// The genesis revision of the agreement, signed by its sender.
+pub(crate) fn sender() -> Revision {
+    parse(include_str!("test_data/DAA_SIG_SENDER_NO_WIT.json"))
+}
+
+/// The revision following [`sender`], signed by the receiver of the agreement.
+pub(crate) fn receiver() -> Revision {
+    parse(include_str!("test_data/DAA_SIG_RECEIVER_NO_WIT.json"))
+}
+
+/// The revision following [`receiver`], signed by the receiver again.
+pub(crate) fn receiver_2() -> Revision {
+    parse(include_str!("test_data/DAA_SIG_RECEIVER_2_NO_WIT.json"))
+}
+
+/// A genesis revision of an agreement without terms, signed by its sender.
+pub(crate) fn sender_no_terms() -> Revision {
+    parse(include_str!("test_data/DAA_SIG_SENDER_NO_WIT_NO_TERM.json"))
+}
+
+/// [`sender`] and [`receiver`], the revision following it.
+pub(crate) fn signed_pair() -> (Revision, Revision) {
+    (sender(), receiver())
+}
+
+fn parse(json: &str) -> Revision {
+    serde_json::from_str(json).expect("failed to parse fixture")
+}
@@ -1,4 +1,5 @@
-use contract_interpreter::*;
+use crate::contracts::*;
+use crate::versions as verifier;
 use std::assert;
 
 #[test]
@@ -94,7 +95,7 @@ fn _test_list_revisions<'a>(
 
     for rev in revision {
         let contract = Contract::from_revision(rev)?.ok()?;
-        let state = Contract::sequence_number(&contract, rev);
+        let state = Contract::signing_state(&contract, rev);
         println!("contract : {:?} \n state: {:?}", contract, state);
         rec_vec.push((contract, state));
     }
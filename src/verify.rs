@@ -0,0 +1,409 @@
+//! Verification of single revisions against the Aqua protocol rules.
+//!
+//! [`verify_revision`] recomputes every hash of a [`Revision`] and checks its signature,
+//! returning a [`VerificationReport`] with the outcome of each check.
+//! [`verify_revision_with`] additionally applies the policy of [`VerifyOptions`].
+
+use crate::models::hash::{Hash, HashMismatch};
+use crate::models::revision::Revision;
+use crate::models::signature_slot::verify_signature_slots;
+use crate::models::storage::Storage;
+use crate::models::transclusion::Transclusion;
+use crate::models::witness_network::WitnessNetwork;
+
+/// Outcome of a single verification check.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// The check succeeded.
+    Passed,
+    /// The check failed, with a human readable reason.
+    Failed(String),
+    /// The check does not apply to this revision (e.g. it has no signature).
+    Skipped,
+}
+
+impl CheckStatus {
+    /// Returns `true` unless the check failed.
+    pub fn is_ok(&self) -> bool {
+        !matches!(self, CheckStatus::Failed(_))
+    }
+}
+
+impl<E: std::fmt::Display> From<Result<(), E>> for CheckStatus {
+    fn from(value: Result<(), E>) -> Self {
+        match value {
+            Ok(()) => CheckStatus::Passed,
+            Err(e) => CheckStatus::Failed(e.to_string()),
+        }
+    }
+}
+
+/// Per-check results of [`verify_revision`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct VerificationReport {
+    /// `content_hash` matches the content slots (and attached file).
+    pub content_hash: CheckStatus,
+    /// `metadata_hash` matches the metadata fields.
+    pub metadata_hash: CheckStatus,
+    /// `signature_hash` matches the signature and public key.
+    pub signature_hash: CheckStatus,
+    /// The signature was made by `public_key` (and `wallet_address`) over the `verification_hash`.
+    pub signature: CheckStatus,
+    /// `signature-slot` keeps the previous entries and records the previous signature.
+    pub signature_slot: CheckStatus,
+    /// `witness_hash` and `witness_event_verification_hash` match the witness fields.
+    pub witness_hash: CheckStatus,
+    /// The witness was recorded on a network of [`VerifyOptions::witness_networks`].
+    pub witness_network: CheckStatus,
+    /// `verification_context` matches the previous revision.
+    pub verification_context: CheckStatus,
+    /// `verification_hash` matches the content, metadata and previous revision.
+    pub verification_hash: CheckStatus,
+}
+
+impl VerificationReport {
+    /// Returns `true` if no check failed.
+    pub fn is_valid(&self) -> bool {
+        [
+            &self.content_hash,
+            &self.metadata_hash,
+            &self.signature_hash,
+            &self.signature,
+            &self.signature_slot,
+            &self.witness_hash,
+            &self.witness_network,
+            &self.verification_context,
+            &self.verification_hash,
+        ]
+        .into_iter()
+        .all(CheckStatus::is_ok)
+    }
+}
+
+/// Policy applied by [`verify_revision_with`] on top of the protocol rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Networks witnesses may be recorded on, e.g. to reject witnesses on
+    /// [deprecated](WitnessNetwork::is_deprecated) networks. Any network is accepted if
+    /// `None`.
+    pub witness_networks: Option<Vec<WitnessNetwork>>,
+}
+
+/// Verifies a single revision with the default [`VerifyOptions`].
+///
+/// # Parameters
+/// - `rev`: The revision to verify.
+/// - `previous`: The revision referenced by `previous_verification_hash`, if any. Its
+///   signature and witness hashes are part of this revision's `verification_hash`.
+pub fn verify_revision(rev: &Revision, previous: Option<&Revision>) -> VerificationReport {
+    verify_revision_with(rev, previous, &VerifyOptions::default())
+}
+
+/// Verifies a single revision, see [`verify_revision`], applying `options`.
+pub fn verify_revision_with(
+    rev: &Revision,
+    previous: Option<&Revision>,
+    options: &VerifyOptions,
+) -> VerificationReport {
+    let metadata = &rev.metadata;
+
+    let content_hash = rev.content.verify_content_hash().into();
+
+    let computed = metadata.compute_metadata_hash();
+    let metadata_hash = check_hash(metadata.metadata_hash, computed, "metadata_hash");
+
+    let verification_hash = match (metadata.previous_verification_hash, previous) {
+        (Some(expected), Some(prev)) if prev.metadata.verification_hash != expected => {
+            CheckStatus::Failed(format!(
+                "previous revision {} is not the referenced {}",
+                prev.metadata.verification_hash, expected
+            ))
+        }
+        (Some(expected), None) => {
+            CheckStatus::Failed(format!("previous revision {expected} not provided"))
+        }
+        (None, Some(prev)) => CheckStatus::Failed(format!(
+            "revision has no previous revision, but {} was provided",
+            prev.metadata.verification_hash
+        )),
+        _ => match rev.compute_verification_hash(previous) {
+            Ok(computed) => check_hash(metadata.verification_hash, computed, "verification_hash"),
+            Err(e) => CheckStatus::Failed(e.to_string()),
+        },
+    };
+
+    let verification_context = match previous {
+        Some(_) => rev.verify_verification_context(previous).into(),
+        None => CheckStatus::Skipped,
+    };
+
+    let (signature_hash, signature) = match &rev.signature {
+        Some(sig) => (
+            check_hash(sig.signature_hash, sig.compute_signature_hash(), "signature_hash"),
+            sig.verify_signer(&metadata.verification_hash).into(),
+        ),
+        None => (CheckStatus::Skipped, CheckStatus::Skipped),
+    };
+
+    let has_slot = |rev: &Revision| rev.content.content.signature_slot().is_some();
+    let signature_slot = if has_slot(rev) || previous.is_some_and(has_slot) {
+        verify_signature_slots(rev, previous).into()
+    } else {
+        CheckStatus::Skipped
+    };
+
+    let (witness_hash, witness_network) = match &rev.witness {
+        Some(wit) => (
+            wit.verify_witness_hash().into(),
+            match &options.witness_networks {
+                Some(networks) if !networks.contains(&wit.witness_network) => {
+                    CheckStatus::Failed(format!(
+                        "witness network {} is not accepted",
+                        wit.witness_network
+                    ))
+                }
+                Some(_) => CheckStatus::Passed,
+                None => CheckStatus::Skipped,
+            },
+        ),
+        None => (CheckStatus::Skipped, CheckStatus::Skipped),
+    };
+
+    VerificationReport {
+        content_hash,
+        metadata_hash,
+        signature_hash,
+        signature,
+        signature_slot,
+        witness_hash,
+        witness_network,
+        verification_context,
+        verification_hash,
+    }
+}
+
+/// Checks that the revision merged in by `rev` exists in `storage`.
+///
+/// Returns the merged-in revision, or `None` if `rev` is no merge.
+pub async fn verify_merge<S: Storage>(
+    storage: &S,
+    rev: &Revision,
+) -> Result<Option<Revision>, MergeVerificationError<S::Error>> {
+    let Some(merge_hash) = rev.metadata.merge_hash else {
+        return Ok(None);
+    };
+    let merged = storage
+        .read(merge_hash)
+        .await
+        .map_err(|source| MergeVerificationError::Unavailable { merge_hash, source })?;
+    let computed = merged.metadata.verification_hash;
+    if computed != merge_hash {
+        return Err(MergeVerificationError::HashMismatch(Box::new(
+            HashMismatch {
+                expected: merge_hash,
+                computed,
+            },
+        )));
+    }
+    Ok(Some(merged))
+}
+
+/// Error returned by [`verify_merge`].
+#[derive(thiserror::Error, Debug)]
+pub enum MergeVerificationError<E: std::error::Error> {
+    /// The storage could not read the revision referenced by `merge_hash`.
+    #[error("merged revision {merge_hash} is not available: {source}")]
+    Unavailable { merge_hash: Hash, source: E },
+
+    /// The storage returned a revision with another `verification_hash`.
+    #[error("storage returned a different revision for merge_hash: {0}")]
+    HashMismatch(Box<HashMismatch>),
+}
+
+/// Resolves a transclusion against `storage` and verifies the referenced revision.
+///
+/// Returns the transcluded revision, or `None` if the transclusion is not pinned to a
+/// `verification_hash`.
+pub async fn verify_transclusion<S: Storage>(
+    storage: &S,
+    transclusion: &Transclusion,
+) -> Result<Option<Revision>, TransclusionError<S::Error>> {
+    let Some(hash) = transclusion.verification_hash else {
+        return Ok(None);
+    };
+    let rev = storage
+        .read(hash)
+        .await
+        .map_err(|source| TransclusionError::Unavailable { hash, source })?;
+    let computed = rev.metadata.verification_hash;
+    if computed != hash {
+        return Err(TransclusionError::HashMismatch(Box::new(HashMismatch {
+            expected: hash,
+            computed,
+        })));
+    }
+    let previous = match rev.metadata.previous_verification_hash {
+        Some(hash) => Some(
+            storage
+                .read(hash)
+                .await
+                .map_err(|source| TransclusionError::Unavailable { hash, source })?,
+        ),
+        None => None,
+    };
+    let report = verify_revision(&rev, previous.as_ref());
+    if !report.is_valid() {
+        return Err(TransclusionError::Invalid {
+            hash,
+            report: Box::new(report),
+        });
+    }
+    Ok(Some(rev))
+}
+
+/// Runs [`verify_transclusion`] for each transclusion, keeping their order.
+pub async fn verify_transclusions<S: Storage>(
+    storage: &S,
+    transclusions: &[Transclusion],
+) -> Vec<Result<Option<Revision>, TransclusionError<S::Error>>> {
+    let mut results = Vec::with_capacity(transclusions.len());
+    for transclusion in transclusions {
+        results.push(verify_transclusion(storage, transclusion).await);
+    }
+    results
+}
+
+/// Error returned by [`verify_transclusion`].
+#[derive(thiserror::Error, Debug)]
+pub enum TransclusionError<E: std::error::Error> {
+    /// The storage could not read the transcluded revision or the revision it follows.
+    #[error("revision {hash} is not available: {source}")]
+    Unavailable { hash: Hash, source: E },
+
+    /// The storage returned a revision with another `verification_hash`.
+    #[error("storage returned a different revision for verification_hash: {0}")]
+    HashMismatch(Box<HashMismatch>),
+
+    /// The transcluded revision failed [`verify_revision`].
+    #[error("transcluded revision {hash} failed verification: {report:?}")]
+    Invalid {
+        hash: Hash,
+        report: Box<VerificationReport>,
+    },
+}
+
+fn check_hash(expected: Hash, computed: Hash, name: &str) -> CheckStatus {
+    if expected == computed {
+        CheckStatus::Passed
+    } else {
+        CheckStatus::Failed(format!("{name} mismatch: {}", HashMismatch { expected, computed }))
+    }
+}
+
+#[test]
+fn verify_signed_revisions() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+
+    let report = verify_revision(&previous, None);
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!(report.witness_hash, CheckStatus::Skipped);
+
+    let report = verify_revision(&rev, Some(&previous));
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!(report.signature, CheckStatus::Passed);
+    assert_eq!(report.signature_slot, CheckStatus::Passed);
+
+    let report = verify_revision(&rev, None);
+    assert!(matches!(report.verification_hash, CheckStatus::Failed(_)));
+    let report = verify_revision(&previous, Some(&rev));
+    assert!(matches!(report.verification_hash, CheckStatus::Failed(_)));
+
+    let mut tampered = rev.clone();
+    let other_key = previous.signature.as_ref().unwrap().public_key;
+    tampered.signature.as_mut().unwrap().public_key = other_key;
+    let report = verify_revision(&tampered, Some(&previous));
+    assert!(matches!(report.signature, CheckStatus::Failed(_)));
+    assert!(matches!(report.signature_hash, CheckStatus::Failed(_)));
+    assert_eq!(report.content_hash, CheckStatus::Passed);
+}
+
+/// Stores `revisions` in a new [`MemoryStorage`](crate::backends::memory::MemoryStorage).
+#[cfg(test)]
+async fn fixture_storage(revisions: Vec<Revision>) -> crate::backends::memory::MemoryStorage<()> {
+    let storage = crate::backends::memory::MemoryStorage::new();
+    for rev in revisions {
+        storage
+            .store(rev, ())
+            .await
+            .expect("failed to store fixture");
+    }
+    storage
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn verify_stored_references() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let storage = fixture_storage(vec![previous.clone(), rev.clone()]).await;
+    let pinned = |hash| Transclusion {
+        dbkey: "DataAccessAgreement".to_string(),
+        ns: 10,
+        verification_hash: Some(hash),
+    };
+
+    let found = verify_transclusion(&storage, &pinned(rev.metadata.verification_hash))
+        .await
+        .expect("stored transclusion rejected");
+    assert_eq!(
+        found.unwrap().metadata.verification_hash,
+        rev.metadata.verification_hash
+    );
+    let unpinned = Transclusion {
+        verification_hash: None,
+        ..pinned(Hash::default())
+    };
+    assert!(verify_transclusion(&storage, &unpinned)
+        .await
+        .unwrap()
+        .is_none());
+    let results = verify_transclusions(&storage, &[unpinned, pinned(Hash::default())]).await;
+    assert!(matches!(
+        results[..],
+        [Ok(None), Err(TransclusionError::Unavailable { .. })]
+    ));
+
+    let without_previous = fixture_storage(vec![previous.clone(), rev.clone()]).await;
+    without_previous.remove(previous.metadata.verification_hash);
+    let err = verify_transclusion(&without_previous, &pinned(rev.metadata.verification_hash))
+        .await
+        .expect_err("transclusion without previous revision accepted");
+    assert!(
+        matches!(err, TransclusionError::Unavailable { hash, .. } if Some(hash) == rev.metadata.previous_verification_hash)
+    );
+
+    let mut tampered = rev.clone();
+    tampered.content.content_hash = Hash::default();
+    let tampered_storage = fixture_storage(vec![previous.clone(), tampered]).await;
+    let err = verify_transclusion(&tampered_storage, &pinned(rev.metadata.verification_hash))
+        .await
+        .expect_err("tampered transclusion accepted");
+    assert!(matches!(err, TransclusionError::Invalid { .. }));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn verify_merge_revision() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+    let storage = fixture_storage(vec![previous.clone(), rev.clone()]).await;
+
+    let mut merge = rev.clone();
+    assert!(verify_merge(&storage, &merge).await.unwrap().is_none());
+    merge.metadata.merge_hash = Some(previous.metadata.verification_hash);
+    assert!(verify_merge(&storage, &merge).await.unwrap().is_some());
+    merge.metadata.merge_hash = Some(Hash::default());
+    assert!(matches!(
+        verify_merge(&storage, &merge).await,
+        Err(MergeVerificationError::Unavailable { .. })
+    ));
+}
@@ -0,0 +1,12 @@
+//! Revision formats of the different Aqua protocol versions.
+//!
+//! [`v1_1`] is the format of the [`crate::models`] types and the one all verification
+//! works on. [`v1_2`] drops the `wallet_address` of signatures, which is derived from the
+//! public key instead, and always states the `verification_context`.
+//!
+//! Only [`v1_1`] is read and written as JSON. No serialized form of v1.2 revisions is
+//! published yet, so [`v1_2`] revisions exist in memory only and formats are not
+//! detected while reading; detection follows once the v1.2 schema is.
+
+pub mod v1_1;
+pub mod v1_2;
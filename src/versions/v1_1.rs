@@ -0,0 +1,4 @@
+//! Aqua protocol v1.1 revisions, the format of the [`crate::models`] types.
+
+pub use crate::models::revision::{Revision, VerificationContext};
+pub use crate::models::signature::RevisionSignature;
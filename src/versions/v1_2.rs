@@ -0,0 +1,150 @@
+//! Aqua protocol v1.2 revisions.
+//!
+//! Compared to v1.1, signatures no longer carry a `wallet_address` (marked for removal
+//! with v1.2 in [`v1_1::RevisionSignature`]) and the `verification_context` is always
+//! stated. Content, metadata and witness are unchanged. No hash covers the
+//! `wallet_address`, so all hashes are the same in both formats.
+//!
+//! The types are not (de)serializable, as no serialized form of v1.2 is published yet.
+
+use ethaddr::Address;
+
+use crate::contracts::AgreementRevision;
+use crate::models::content::RevisionContent;
+use crate::models::hash::Hash;
+use crate::models::metadata::RevisionMetadata;
+use crate::models::public_key::PublicKey;
+use crate::models::signature::{Signature, SignatureError};
+use crate::models::witness::RevisionWitness;
+use crate::versions::v1_1;
+
+pub use crate::models::revision::VerificationContext;
+
+/// A v1.2 revision of a document on an Aqua chain.
+#[derive(Clone, Debug)]
+pub struct Revision {
+    /// Which hashes of the previous revision are part of the `verification_hash`.
+    pub verification_context: VerificationContext,
+    /// The content of the revision.
+    pub content: RevisionContent,
+    /// Metadata associated with the revision.
+    pub metadata: RevisionMetadata,
+    /// Optional signature of the revision.
+    pub signature: Option<RevisionSignature>,
+    /// Optional witness data for the revision.
+    pub witness: Option<RevisionWitness>,
+}
+
+/// A v1.2 signature, without the `wallet_address` of v1.1.
+#[derive(Clone, Debug)]
+pub struct RevisionSignature {
+    /// Signature over the `verification_hash` of the revision.
+    pub signature: Signature,
+    /// Public key the signature was made with.
+    pub public_key: PublicKey,
+    /// Hash of `signature` and `public_key`, as in v1.1.
+    pub signature_hash: Hash,
+}
+
+impl RevisionSignature {
+    /// The wallet address of `public_key`.
+    pub fn wallet_address(&self) -> Address {
+        Address::from(self.public_key)
+    }
+}
+
+impl From<&RevisionSignature> for v1_1::RevisionSignature {
+    fn from(sig: &RevisionSignature) -> Self {
+        v1_1::RevisionSignature {
+            wallet_address: sig.wallet_address(),
+            signature: sig.signature,
+            public_key: sig.public_key,
+            signature_hash: sig.signature_hash,
+        }
+    }
+}
+
+impl AgreementRevision for Revision {
+    fn main(&self) -> Option<&str> {
+        self.content.content.main()
+    }
+
+    /// Verifies the signature against its public key, which the wallet address is derived from.
+    fn signer(&self) -> Option<Result<Address, SignatureError>> {
+        let signature = v1_1::RevisionSignature::from(self.signature.as_ref()?);
+        Some(
+            signature
+                .verify_signer(&self.metadata.verification_hash)
+                .map(|()| signature.wallet_address),
+        )
+    }
+}
+
+/// Converts a v1.1 revision into v1.2.
+///
+/// # Parameters
+/// - `rev`: The revision to convert.
+/// - `previous`: The revision `rev` follows, used to derive the `verification_context`.
+/// - `verification_context`: States the context explicitly, e.g. when `previous` is not
+///   available. Without both, the context stored in `rev` is kept.
+///
+/// The `wallet_address` of the signature is dropped, as v1.2 derives it from the public key.
+pub fn rev_v1_1_to_rev_v1_2(
+    rev: &v1_1::Revision,
+    previous: Option<&v1_1::Revision>,
+    verification_context: Option<VerificationContext>,
+) -> Revision {
+    let verification_context = match (verification_context, previous) {
+        (Some(context), _) => context,
+        (None, Some(_)) => VerificationContext::from_previous(previous),
+        (None, None) => rev.verification_context,
+    };
+    Revision {
+        verification_context,
+        content: rev.content.clone(),
+        metadata: rev.metadata.clone(),
+        signature: rev.signature.as_ref().map(|sig| RevisionSignature {
+            signature: sig.signature,
+            public_key: sig.public_key,
+            signature_hash: sig.signature_hash,
+        }),
+        witness: rev.witness.clone(),
+    }
+}
+
+/// Converts a v1.2 revision into v1.1, deriving the `wallet_address` of the signature.
+pub fn rev_v1_2_to_rev_v1_1(rev: Revision) -> v1_1::Revision {
+    v1_1::Revision {
+        verification_context: rev.verification_context,
+        content: rev.content,
+        metadata: rev.metadata,
+        signature: rev.signature.as_ref().map(v1_1::RevisionSignature::from),
+        witness: rev.witness,
+    }
+}
+
+#[test]
+fn convert_signed_revisions() {
+    let (previous, rev) = crate::tests::fixtures::signed_pair();
+
+    let converted = rev_v1_1_to_rev_v1_2(&rev, Some(&previous), None);
+    assert!(converted.verification_context.has_previous_signature);
+    assert!(!converted.verification_context.has_previous_witness);
+    let wallet = rev.signature.as_ref().unwrap().wallet_address;
+    assert_eq!(
+        converted.signature.as_ref().unwrap().wallet_address(),
+        wallet
+    );
+
+    // Converting back reproduces the revision, which still verifies.
+    let converted = rev_v1_2_to_rev_v1_1(converted);
+    assert_eq!(
+        serde_json::to_value(&converted).unwrap(),
+        serde_json::to_value(&rev).unwrap()
+    );
+    assert!(crate::verify::verify_revision(&converted, Some(&previous)).is_valid());
+
+    let explicit = VerificationContext::default();
+    let converted = rev_v1_1_to_rev_v1_2(&rev, Some(&previous), Some(explicit));
+    assert_eq!(converted.verification_context, explicit);
+}
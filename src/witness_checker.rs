@@ -0,0 +1,451 @@
+//! Lookup of witness transactions on an Ethereum network.
+//!
+//! A [`WitnessChecker`] queries a JSON-RPC endpoint with `eth_getTransactionByHash`
+//! and confirms that the transaction of a [`RevisionWitness`] really carries its
+//! `witness_event_verification_hash`. A [`WitnessRouter`] dispatches witnesses to the
+//! checker of their network.
+
+use std::collections::HashMap;
+
+use ethaddr::Address;
+
+use crate::models::hash::{Hash, HashMismatch};
+use crate::models::tx_hash::TxHash;
+use crate::models::witness::RevisionWitness;
+use crate::models::witness_network::WitnessNetwork;
+
+/// Function selector of `witness(bytes)` on the Aqua witness smart contract.
+pub const WITNESS_SELECTOR: [u8; 4] = [0x9c, 0xef, 0x4e, 0xa1];
+
+/// The parts of an Ethereum transaction needed to check a witness.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Transaction {
+    /// Hash of the transaction.
+    pub hash: TxHash,
+    /// Account that sent the transaction.
+    pub from: Address,
+    /// Contract the transaction was sent to, `None` for contract creations.
+    pub to: Option<Address>,
+    /// Hex-encoded calldata of the transaction.
+    pub input: String,
+}
+
+impl Transaction {
+    /// Decodes the `witness_event_verification_hash` from the calldata of a
+    /// `witness(bytes)` call: the 64 bytes following the selector. Any bytes after them
+    /// are ignored, like the reference verifier does.
+    ///
+    /// # Errors
+    /// Returns [`WitnessCheckError::InvalidCalldata`] if the calldata is not such a call.
+    pub fn witness_event_verification_hash(&self) -> Result<Hash, WitnessCheckError> {
+        let data = self
+            .input
+            .strip_prefix("0x")
+            .and_then(|input| hex::decode(input).ok())
+            .ok_or(WitnessCheckError::InvalidCalldata)?;
+        match data.get(..4 + 64) {
+            Some(call) if call[..4] == WITNESS_SELECTOR => {
+                let hash: [u8; 64] = call[4..].try_into().unwrap();
+                Ok(Hash::from(hash))
+            }
+            _ => Err(WitnessCheckError::InvalidCalldata),
+        }
+    }
+}
+
+/// Checks witnesses of one network against its JSON-RPC endpoint.
+#[derive(Debug, Clone)]
+pub struct WitnessChecker {
+    client: reqwest::Client,
+    rpc_url: url::Url,
+    network: WitnessNetwork,
+    contract_address: Option<Address>,
+    sender: Option<Address>,
+}
+
+impl WitnessChecker {
+    /// Creates a checker for witnesses on `network`, using the JSON-RPC endpoint at
+    /// `rpc_url`. Transactions must be sent to the
+    /// [default contract](WitnessNetwork::default_contract_address) of the network, if
+    /// one is known.
+    pub fn new(network: WitnessNetwork, rpc_url: url::Url) -> Self {
+        WitnessChecker {
+            client: reqwest::Client::new(),
+            rpc_url,
+            contract_address: network.default_contract_address(),
+            network,
+            sender: None,
+        }
+    }
+
+    /// Uses the given HTTP client for requests.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Requires witness transactions to be sent to `contract_address` instead of the
+    /// default contract of the network.
+    pub fn with_contract_address(mut self, contract_address: Address) -> Self {
+        self.contract_address = Some(contract_address);
+        self
+    }
+
+    /// Requires witness transactions to be sent by `sender`.
+    pub fn with_sender(mut self, sender: Address) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// The `witness_network` this checker handles.
+    pub fn network(&self) -> &WitnessNetwork {
+        &self.network
+    }
+
+    /// Fetches a transaction with `eth_getTransactionByHash`.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the node answers with an error or the
+    /// transaction is unknown.
+    pub async fn get_transaction(
+        &self,
+        tx_hash: &TxHash,
+    ) -> Result<Transaction, WitnessCheckError> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getTransactionByHash",
+            "params": [tx_hash],
+        });
+        let response: RpcResponse = self
+            .client
+            .post(self.rpc_url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            return Err(WitnessCheckError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        response
+            .result
+            .ok_or(WitnessCheckError::TransactionNotFound(*tx_hash))
+    }
+
+    /// Checks that the witness transaction exists on the network and carries the
+    /// `witness_event_verification_hash` of `witness`, and that it was sent by
+    /// `sender_account_address` to `smart_contract_address` if `witness` names them (and
+    /// to the configured contract and by the configured sender, if any).
+    ///
+    /// # Errors
+    /// See [`WitnessCheckError`].
+    pub async fn check(&self, witness: &RevisionWitness) -> Result<(), WitnessCheckError> {
+        if witness.witness_network != self.network {
+            return Err(WitnessCheckError::NetworkMismatch {
+                expected: self.network.clone(),
+                found: witness.witness_network.clone(),
+            });
+        }
+        let tx = self
+            .get_transaction(&witness.witness_event_transaction_hash)
+            .await?;
+        let computed = tx.witness_event_verification_hash()?;
+        if computed != witness.witness_event_verification_hash {
+            return Err(WitnessCheckError::VerificationHashMismatch(Box::new(
+                HashMismatch {
+                    expected: witness.witness_event_verification_hash,
+                    computed,
+                },
+            )));
+        }
+        for expected in [witness.smart_contract_address, self.contract_address]
+            .into_iter()
+            .flatten()
+        {
+            if tx.to != Some(expected) {
+                return Err(WitnessCheckError::ContractMismatch {
+                    expected,
+                    found: tx.to,
+                });
+            }
+        }
+        for expected in [witness.sender_account_address, self.sender]
+            .into_iter()
+            .flatten()
+        {
+            if tx.from != expected {
+                return Err(WitnessCheckError::SenderMismatch {
+                    expected,
+                    found: tx.from,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Routes witnesses to the [`WitnessChecker`] of their `witness_network`.
+#[derive(Debug, Clone, Default)]
+pub struct WitnessRouter {
+    checkers: HashMap<WitnessNetwork, WitnessChecker>,
+}
+
+impl WitnessRouter {
+    /// Creates a router without any checkers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `checker`, replacing any checker for the same network.
+    pub fn with_checker(mut self, checker: WitnessChecker) -> Self {
+        self.checkers.insert(checker.network.clone(), checker);
+        self
+    }
+
+    /// The checker for `network`, if configured.
+    pub fn checker(&self, network: &WitnessNetwork) -> Option<&WitnessChecker> {
+        self.checkers.get(network)
+    }
+
+    /// Checks `witness` with the checker of its network.
+    ///
+    /// # Errors
+    /// Returns [`WitnessCheckError::UnsupportedNetwork`] if no checker is configured for
+    /// the network, otherwise see [`WitnessChecker::check`].
+    pub async fn check(&self, witness: &RevisionWitness) -> Result<(), WitnessCheckError> {
+        self.checker(&witness.witness_network)
+            .ok_or_else(|| WitnessCheckError::UnsupportedNetwork(witness.witness_network.clone()))?
+            .check(witness)
+            .await
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RpcResponse {
+    result: Option<Transaction>,
+    error: Option<RpcError>,
+}
+
+#[derive(serde::Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// Error types for [`WitnessChecker`].
+#[derive(thiserror::Error, Debug)]
+pub enum WitnessCheckError {
+    /// The HTTP request to the endpoint failed.
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The endpoint answered with a JSON-RPC error.
+    #[error("rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    /// The network does not know the witness transaction.
+    #[error("transaction {0} not found")]
+    TransactionNotFound(TxHash),
+
+    /// The witness was recorded on another network than the one checked.
+    #[error("witness is on network {found}, checker is for {expected}")]
+    NetworkMismatch {
+        expected: WitnessNetwork,
+        found: WitnessNetwork,
+    },
+
+    /// No checker is configured for the network of the witness.
+    #[error("no witness checker for network {0}")]
+    UnsupportedNetwork(WitnessNetwork),
+
+    /// The transaction calldata is not a `witness(bytes)` call.
+    #[error("transaction calldata is not a witness call")]
+    InvalidCalldata,
+
+    /// The transaction carries another verification hash than the witness.
+    #[error("witness_event_verification_hash mismatch: {0}")]
+    VerificationHashMismatch(Box<HashMismatch>),
+
+    /// The transaction was sent to another contract.
+    #[error("transaction was sent to {found:?}, not contract {expected}")]
+    ContractMismatch {
+        expected: Address,
+        found: Option<Address>,
+    },
+
+    /// The transaction was sent by another account.
+    #[error("transaction was sent by {found}, not {expected}")]
+    SenderMismatch { expected: Address, found: Address },
+}
+
+#[test]
+fn decode_witness_calldata() {
+    let hash = Hash::from([5; 64]);
+    let transaction = |input: String| Transaction {
+        hash: TxHash::default(),
+        from: Address::default(),
+        to: None,
+        input,
+    };
+    let call = format!("0x{}{hash}", hex::encode(WITNESS_SELECTOR));
+    assert_eq!(
+        transaction(call.clone()).witness_event_verification_hash().unwrap(),
+        hash
+    );
+    assert_eq!(
+        transaction(format!("{call}00000000"))
+            .witness_event_verification_hash()
+            .unwrap(),
+        hash
+    );
+    for input in [call[..call.len() - 2].to_string(), call[2..].to_string(), format!("{call}0")] {
+        assert!(matches!(
+            transaction(input).witness_event_verification_hash(),
+            Err(WitnessCheckError::InvalidCalldata)
+        ));
+    }
+}
+
+/// Serves a single canned JSON-RPC `result` for every request, returning the endpoint URL.
+#[cfg(test)]
+fn mock_rpc_server(result: serde_json::Value) -> url::Url {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap())
+        .parse()
+        .unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(request["method"], "eth_getTransactionByHash");
+            let body =
+                serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                    .to_string();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    url
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn check_witness_against_mock_rpc() {
+    use crate::models::witness::{MerkleNode, WitnessSource};
+
+    let contract: Address = "0x45f59310ADD88E6d23ca58A0Fa7A55BEE6d2a611"
+        .parse()
+        .unwrap();
+    let sender: Address = "0x1ad5da43de60aa7d311f9b4e9c3342c155e6d2e0"
+        .parse()
+        .unwrap();
+    let mut witness = RevisionWitness {
+        witness_event_id: Some(1),
+        domain_id: None,
+        domain_snapshot_title: Some("Domain Snapshot".to_string()),
+        domain_snapshot_genesis_hash: Hash::from([1; 64]),
+        merkle_root: Hash::from([2; 64]),
+        witness_network: WitnessNetwork::Sepolia,
+        smart_contract_address: Some(contract),
+        witness_event_transaction_hash: TxHash::from([3; 32]),
+        sender_account_address: Some(sender),
+        source: Some(WitnessSource::Local),
+        witness_event_verification_hash: Hash::default(),
+        witness_hash: Hash::default(),
+        structured_merkle_proof: Vec::<MerkleNode>::new(),
+    };
+    witness.witness_event_verification_hash = witness.compute_witness_event_verification_hash();
+
+    let url = mock_rpc_server(serde_json::json!({
+        "hash": witness.witness_event_transaction_hash,
+        "from": sender,
+        "to": contract,
+        "input": format!("0x{}{}", hex::encode(WITNESS_SELECTOR), witness.witness_event_verification_hash),
+    }));
+    let checker = WitnessChecker::new(WitnessNetwork::Sepolia, url)
+        .with_contract_address(contract)
+        .with_sender(sender);
+    checker
+        .check(&witness)
+        .await
+        .expect("valid witness rejected");
+
+    let other_checker = checker.clone().with_sender(contract);
+    assert!(matches!(
+        other_checker.check(&witness).await,
+        Err(WitnessCheckError::SenderMismatch { .. })
+    ));
+
+    let mut forged = witness.clone();
+    forged.sender_account_address = Some(contract);
+    assert!(matches!(
+        checker.check(&forged).await,
+        Err(WitnessCheckError::SenderMismatch { .. })
+    ));
+    forged.smart_contract_address = Some(sender);
+    assert!(matches!(
+        checker.check(&forged).await,
+        Err(WitnessCheckError::ContractMismatch { .. })
+    ));
+
+    let mut tampered = witness.clone();
+    tampered.merkle_root = Hash::from([4; 64]);
+    tampered.witness_event_verification_hash = tampered.compute_witness_event_verification_hash();
+    assert!(matches!(
+        checker.check(&tampered).await,
+        Err(WitnessCheckError::VerificationHashMismatch(_))
+    ));
+
+    tampered.witness_network = WitnessNetwork::Mainnet;
+    assert!(matches!(
+        checker.check(&tampered).await,
+        Err(WitnessCheckError::NetworkMismatch { .. })
+    ));
+
+    let router = WitnessRouter::new().with_checker(checker.clone());
+    router
+        .check(&witness)
+        .await
+        .expect("valid witness rejected");
+    assert!(matches!(
+        router.check(&tampered).await,
+        Err(WitnessCheckError::UnsupportedNetwork(
+            WitnessNetwork::Mainnet
+        ))
+    ));
+
+    let url = mock_rpc_server(serde_json::Value::Null);
+    assert!(matches!(
+        WitnessChecker::new(WitnessNetwork::Sepolia, url)
+            .check(&witness)
+            .await,
+        Err(WitnessCheckError::TransactionNotFound(_))
+    ));
+}